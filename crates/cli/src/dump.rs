@@ -0,0 +1,328 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const FORMAT: &str = "quicklook-dump";
+
+/// Bumped on incompatible changes, `read` has to keep accepting dumps of older versions.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DumpFormat {
+    /// a header line, then one record per line
+    Ndjson,
+    /// a single object
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedSource {
+    pub name: String,
+    pub source: String,
+    pub version: String,
+    /// missing from dumps made before modes were stored
+    #[serde(default)]
+    pub is_aram: bool,
+    #[serde(default)]
+    pub is_urf: bool,
+    /// so a retired source is still deleted after its grace period once imported
+    #[serde(default)]
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedBuild {
+    pub source: String,
+    pub version: String,
+    pub champion_alias: String,
+    pub champion_id: String,
+    pub content: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedLog {
+    pub action: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A line of an NDJSON dump after the header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Source(DumpedSource),
+    Build(DumpedBuild),
+    Log(DumpedLog),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dump {
+    #[serde(flatten)]
+    pub header: Header,
+    pub sources: Vec<DumpedSource>,
+    pub builds: Vec<DumpedBuild>,
+    pub logs: Vec<DumpedLog>,
+}
+
+impl Dump {
+    pub fn new(sources: Vec<DumpedSource>, builds: Vec<DumpedBuild>, logs: Vec<DumpedLog>) -> Self {
+        let header = Header {
+            format: FORMAT.to_string(),
+            version: FORMAT_VERSION,
+            exported_at: Utc::now(),
+        };
+        Self {
+            header,
+            sources,
+            builds,
+            logs,
+        }
+    }
+
+    /// Problems that would make the import fail or serve broken builds.
+    /// `known_sources` are the ones already in the database.
+    pub fn validate(&self, known_sources: &HashSet<String>) -> Vec<String> {
+        let mut errors = vec![];
+
+        let mut sources = known_sources.clone();
+        for s in self.sources.iter() {
+            if s.source.is_empty() || s.name.is_empty() {
+                errors.push(format!("source {:?} has no name or id", s.source));
+            }
+            sources.insert(s.source.clone());
+        }
+
+        let mut seen = HashSet::new();
+        for b in self.builds.iter() {
+            let key = format!("{}/{}", b.source, b.champion_alias);
+            if b.champion_alias.is_empty() || b.champion_id.is_empty() {
+                errors.push(format!("build {key} has no champion alias or id"));
+            }
+            if !sources.contains(&b.source) {
+                errors.push(format!(
+                    "build {key} belongs to unknown source {}",
+                    b.source
+                ));
+            }
+            if !seen.insert((&b.source, &b.champion_id, &b.champion_alias)) {
+                errors.push(format!("build {key} appears more than once"));
+            }
+            match serde_json::from_value::<Vec<service::Build>>(b.content.clone()) {
+                Ok(builds) if builds.is_empty() => errors.push(format!("build {key} is empty")),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("build {key} is invalid: {e}")),
+            }
+        }
+
+        errors
+    }
+}
+
+pub fn write(dump: &Dump, format: DumpFormat, out: &str) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(fs::File::create(out)?);
+    match format {
+        DumpFormat::Json => serde_json::to_writer(&mut writer, dump)?,
+        DumpFormat::Ndjson => {
+            serde_json::to_writer(&mut writer, &dump.header)?;
+            writeln!(writer)?;
+            let records = dump
+                .sources
+                .iter()
+                .cloned()
+                .map(Record::Source)
+                .chain(dump.builds.iter().cloned().map(Record::Build))
+                .chain(dump.logs.iter().cloned().map(Record::Log));
+            for record in records {
+                serde_json::to_writer(&mut writer, &record)?;
+                writeln!(writer)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a dump in either format, an NDJSON one starts with a line holding only the header.
+pub fn read(path: &str) -> anyhow::Result<Dump> {
+    let mut lines = BufReader::new(fs::File::open(path)?).lines();
+    let first_line = lines.next().transpose()?.unwrap_or_default();
+    let header = serde_json::from_str::<Value>(&first_line)
+        .ok()
+        .filter(|v| v.get("sources").is_none());
+
+    let dump = match header {
+        Some(header) => {
+            let header = serde_json::from_value::<Header>(header)?;
+            check_version(&header)?;
+            let mut dump = Dump {
+                header,
+                sources: vec![],
+                builds: vec![],
+                logs: vec![],
+            };
+            for (idx, line) in lines.enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str::<Record>(&line)
+                    .map_err(|e| anyhow::anyhow!("line {}: {e}", idx + 2))?;
+                match record {
+                    Record::Source(s) => dump.sources.push(s),
+                    Record::Build(b) => dump.builds.push(b),
+                    Record::Log(l) => dump.logs.push(l),
+                }
+            }
+            dump
+        }
+        None => {
+            let content = fs::read_to_string(path)?;
+            let header = serde_json::from_str::<Header>(&content)?;
+            check_version(&header)?;
+            serde_json::from_str::<Dump>(&content)?
+        }
+    };
+
+    Ok(dump)
+}
+
+fn check_version(header: &Header) -> anyhow::Result<()> {
+    if header.format != FORMAT {
+        anyhow::bail!("not a {FORMAT} file: {}", header.format);
+    }
+    if header.version == 0 || header.version > FORMAT_VERSION {
+        anyhow::bail!(
+            "unsupported dump version {}, this build reads up to {FORMAT_VERSION}",
+            header.version
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(id: &str) -> DumpedSource {
+        DumpedSource {
+            name: id.to_string(),
+            source: id.to_string(),
+            version: String::from("1.2.0"),
+            is_aram: false,
+            is_urf: true,
+            retired_at: None,
+        }
+    }
+
+    fn build(source: &str, alias: &str) -> DumpedBuild {
+        DumpedBuild {
+            source: source.to_string(),
+            version: String::from("1.2.0"),
+            champion_alias: alias.to_string(),
+            champion_id: String::from("103"),
+            content: serde_json::to_value(vec![service::Build::default()]).unwrap(),
+        }
+    }
+
+    fn sample() -> Dump {
+        let retired = DumpedSource {
+            retired_at: Some(Utc::now()),
+            ..source("u-gg")
+        };
+        let logs = vec![DumpedLog {
+            action: String::from("sync_builds"),
+            created_at: Utc::now(),
+        }];
+        Dump::new(
+            vec![source("op-gg"), retired],
+            vec![build("op-gg", "Ahri")],
+            logs,
+        )
+    }
+
+    /// A file of its own for each test, as they run in parallel.
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("quicklook-{}-{name}", std::process::id()));
+        path.to_string_lossy().into_owned()
+    }
+
+    fn round_trip(format: DumpFormat, name: &str) {
+        let path = temp_path(name);
+        let dump = sample();
+        write(&dump, format, &path).unwrap();
+        let read = read(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.unwrap(), dump);
+    }
+
+    #[test]
+    fn ndjson_round_trips() {
+        round_trip(DumpFormat::Ndjson, "round-trip.ndjson");
+    }
+
+    #[test]
+    fn json_round_trips() {
+        round_trip(DumpFormat::Json, "round-trip.json");
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        for version in [0, FORMAT_VERSION + 1] {
+            let path = temp_path(&format!("version-{version}.ndjson"));
+            let mut dump = sample();
+            dump.header.version = version;
+            write(&dump, DumpFormat::Ndjson, &path).unwrap();
+            let read = read(&path);
+            fs::remove_file(&path).unwrap();
+
+            let e = read.unwrap_err().to_string();
+            assert!(e.contains("unsupported dump version"), "{e}");
+        }
+    }
+
+    #[test]
+    fn reads_sources_without_retired_at() {
+        let json = r#"{"type":"source","name":"OP.GG","source":"op-gg","version":"1.2.0"}"#;
+        let Record::Source(source) = serde_json::from_str::<Record>(json).unwrap() else {
+            panic!("not a source");
+        };
+        assert_eq!(source.retired_at, None);
+    }
+
+    #[test]
+    fn finds_duplicate_builds() {
+        let mut dump = sample();
+        dump.builds.push(build("op-gg", "Ahri"));
+
+        let errors = dump.validate(&HashSet::new());
+        assert_eq!(errors, ["build op-gg/Ahri appears more than once"]);
+    }
+
+    #[test]
+    fn finds_builds_of_unknown_sources() {
+        let mut dump = sample();
+        dump.builds.push(build("mobalytics", "Ahri"));
+
+        let errors = dump.validate(&HashSet::new());
+        assert_eq!(
+            errors,
+            ["build mobalytics/Ahri belongs to unknown source mobalytics"]
+        );
+        // the source may already be in the database instead of the dump
+        assert!(dump
+            .validate(&HashSet::from([String::from("mobalytics")]))
+            .is_empty());
+    }
+}
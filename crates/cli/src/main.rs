@@ -1,6 +1,13 @@
+mod dump;
+
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::Path;
-use db::models::NewBuild;
+use std::time::{Duration, SystemTime};
+use db::models::{NewBuild, SyncEvent};
+use service::webhook::WebhookEvent;
+
+use crate::dump::{Dump, DumpFormat, DumpedBuild, DumpedLog, DumpedSource};
 
 use clap::{Parser, Subcommand};
 use kv_log_macro as log;
@@ -10,7 +17,108 @@ use serde_json::value::to_value;
 enum Commands {
     InitDB,
     SyncSource,
-    SyncBuild,
+    SyncDdragon {
+        /// Data Dragon locales to store, e.g. `--locale zh_CN --locale ko_KR`
+        #[arg(long = "locale", default_value = service::DEFAULT_LOCALE)]
+        locales: Vec<String>,
+    },
+    SyncBuild {
+        /// Drop builds with runes or items unknown to the current patch instead of only warning
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Load a source's builds from a local package instead of npm, e.g. to test it before publishing
+    Load {
+        #[arg(long)]
+        source: String,
+        /// A package directory, or a `.tgz` made by `npm pack`
+        #[arg(long)]
+        path: String,
+        /// Drop builds with runes or items unknown to the synced patch instead of only warning
+        #[arg(long)]
+        strict: bool,
+        /// Load a source that isn't in the database yet, it isn't listed until sync-source or import adds it
+        #[arg(long)]
+        allow_unknown: bool,
+    },
+    /// Retire sources gone from the source list, and delete their builds once the grace period is over
+    Prune {
+        /// Days the builds of a retired source are still served
+        #[arg(long, default_value_t = 7)]
+        grace_days: u64,
+        /// Only report what would be retired and deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Compare a champion's builds between two package versions of a source
+    Diff {
+        #[arg(long)]
+        source: String,
+        #[arg(long)]
+        champion: String,
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+    },
+    /// Write a source's item builds as League client item-set files
+    ExportItemsets {
+        #[arg(long)]
+        source: String,
+        #[arg(long)]
+        out: String,
+    },
+    /// Dump sources, builds and logs to a file, e.g. for backups or seeding another database
+    Export {
+        #[arg(long, value_enum, default_value_t = DumpFormat::Ndjson)]
+        format: DumpFormat,
+        #[arg(long)]
+        out: String,
+    },
+    /// Load a file written by `export`, in either format
+    Import {
+        #[arg(long)]
+        path: String,
+        /// Delete every source, build and log before importing
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Manage the webhooks notified when a source or Data Dragon gets a new version
+    Webhook {
+        #[command(subcommand)]
+        command: WebhookCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum WebhookCommands {
+    Add {
+        #[arg(long)]
+        url: String,
+        /// Sign payloads with HMAC-SHA256 in the `X-Quicklook-Signature` header
+        #[arg(long)]
+        secret: Option<String>,
+        /// Events to deliver, e.g. `--event source_updated`, all of them when omitted
+        #[arg(long = "event")]
+        events: Vec<String>,
+    },
+    List,
+    Remove {
+        #[arg(long)]
+        id: i32,
+    },
+    /// Send a `ping` event to check that a receiver works
+    Test {
+        #[arg(long)]
+        id: i32,
+    },
+    /// Show the latest deliveries of a webhook
+    Deliveries {
+        #[arg(long)]
+        id: i32,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
 }
 
 #[derive(Parser)]
@@ -29,42 +137,131 @@ async fn main() -> anyhow::Result<()> {
 
     let cli = Cli::parse();
 
-    let mut pg_conn = db::establish_connection().await?;
+    // diffing only reads the CDN, so it works without a database
+    if let Some(Commands::Diff {
+        source,
+        champion,
+        from,
+        to,
+    }) = &cli.command
+    {
+        return diff(source, champion, from, to).await;
+    }
 
-    let source_list = service::list_sources().await?;
+    let mut pg_conn = db::establish_connection().await?;
+    let pool = db::make_db_pool()?;
 
     match &cli.command {
         Some(Commands::SyncSource) => {
             info!("started sync sources");
+            let source_list = service::list_sources().await?;
 
             let new_sources = source_list
                 .iter()
-                .map(|source| {
-                    let name = source.label.clone();
-                    let source = source.value.clone();
+                .map(|item| {
+                    let name = item.label.clone();
+                    let source = item.value.clone();
                     let version = String::from("1.0.0");
                     db::models::NewSource {
                         name,
                         source,
                         version,
+                        is_aram: item.is_aram.unwrap_or_default(),
+                        is_urf: item.is_urf.unwrap_or_default(),
+                        retired_at: None,
                     }
                 })
                 .collect::<Vec<db::models::NewSource>>();
+            let sources = new_sources.iter().map(|s| s.source.clone()).collect();
             let total = db::insert_many_sources(&mut pg_conn, new_sources).await?;
             info!("inserted: {total}");
+            let known = db::list_sources(pool).await?;
+            let retiring = retiring_sources(&known, &missing_sources(&known, &source_list));
+            if !retiring.is_empty() {
+                let retired = db::retire_sources(&mut pg_conn, retiring.clone()).await?;
+                info!("retired: {retired}, {:?}", retiring);
+            }
             db::insert_log(&mut pg_conn, String::from("sync_sources")).await?;
+            notify_sync(
+                &mut pg_conn,
+                SyncEvent {
+                    action: String::from("sync_sources"),
+                    sources,
+                    versions: BTreeMap::new(),
+                },
+            )
+            .await;
+
+            Ok(())
+        }
+        Some(Commands::SyncDdragon { locales }) => {
+            let version = service::get_latest_version().await?;
+            info!("started sync data dragon {version}, locales: {:?}", locales);
+
+            let languages = service::list_languages().await?;
+            let old_version = db::list_ddragon_versions(pool.clone())
+                .await?
+                .into_iter()
+                .next();
+            let mut new_files = vec![];
+            let mut versions = BTreeMap::new();
+            for locale in locales.iter() {
+                if !languages.contains(locale) {
+                    error!("unknown locale {locale}, skipped");
+                    continue;
+                }
+                versions.insert(locale.clone(), version.clone());
+                for file in service::DATA_DRAGON_FILES {
+                    let content = service::get_data_dragon_file(&version, locale, file).await?;
+                    new_files.push(db::models::NewDataDragonFile {
+                        version: version.clone(),
+                        file: file.to_string(),
+                        content,
+                        locale: locale.clone(),
+                    });
+                }
+            }
+            let total = db::upsert_many_ddragon_files(&mut pg_conn, new_files).await?;
+            info!("inserted: {total}");
+            db::insert_log(&mut pg_conn, String::from("sync_ddragon")).await?;
+            notify_sync(
+                &mut pg_conn,
+                SyncEvent {
+                    action: String::from("sync_ddragon"),
+                    sources: vec![],
+                    versions,
+                },
+            )
+            .await;
+
+            // a patch only counts once some of its files made it in
+            if total > 0 && old_version.as_ref() != Some(&version) {
+                let event = WebhookEvent::DdragonPatch {
+                    old_version,
+                    new_version: version,
+                };
+                deliver_webhooks(pool, vec![event]).await?;
+            }
 
             Ok(())
         }
-        Some(Commands::SyncBuild) => {
+        Some(Commands::SyncBuild { strict }) => {
             info!("started sync builds");
-            let champion_map_resp = service::list_all_champions().await?;
+            let source_list = service::list_sources().await?;
+            let old_versions = db::latest_source_versions(pool.clone()).await?;
+            let champion_map_resp = service::list_all_champions(service::DEFAULT_LOCALE).await?;
             info!(
                 "version {}, total: {}",
                 champion_map_resp.version,
                 champion_map_resp.data.len()
             );
+            let (rune_styles, items) = tokio::try_join!(
+                service::list_runes_reforged(&champion_map_resp.version, service::DEFAULT_LOCALE),
+                service::list_items(&champion_map_resp.version, service::DEFAULT_LOCALE),
+            )?;
 
+            let mut versions = BTreeMap::new();
+            let mut events = vec![];
             for item in source_list.iter() {
                 let source = item.value.clone();
                 let (latest_version, tarball_url) = match service::get_remote_package_data(&source).await {
@@ -77,16 +274,12 @@ async fn main() -> anyhow::Result<()> {
                 let source_version = latest_version.clone();
                 info!("[{}] latest version: {}, ready to download: {}", &source, &latest_version, &tarball_url);
 
-                let output_dir = format!("./output/{}", &source);
-                let output_path = Path::new(&output_dir);
-                if output_path.exists() {
-                    match fs::remove_dir_all(output_path) {
-                        Ok(_) => info!("removed {output_dir}"),
-                        Err(e) => error!("Error removing {output_dir}: {}", e),
+                let output_dir = match prepare_output_dir(&source) {
+                    Ok(dir) => dir,
+                    Err(e) => {
+                        error!("{e}, skipped");
+                        continue;
                     }
-                }
-                if let Err(e) = fs::create_dir_all(output_path) {
-                    error!("create {output_dir} failed: {}", e);
                 };
                 if let Err(e) = service::download_and_extract_tgz(&tarball_url, &output_dir).await {
                     error!("download & extract failed from {}, {}", &tarball_url, e);
@@ -95,29 +288,785 @@ async fn main() -> anyhow::Result<()> {
                 info!("downloaded {tarball_url}");
 
                 let extracted_dir = format!("{}/package", &output_dir);
-                let files = service::read_from_local_folder(&extracted_dir).await?;
-                let new_builds = files.iter().map(|builds| {
-                    let first_build = builds.first().unwrap();
-                    NewBuild {
-                        source: item.value.clone(),
-                        version: source_version.clone(),
-                        champion_id: first_build.id.clone(),
-                        champion_alias: first_build.alias.clone(),
-                        content: to_value(builds).unwrap(),
-                    }
-                }).collect();
+                let new_builds = read_package_builds(
+                    &source,
+                    &source_version,
+                    &extracted_dir,
+                    Some((&rune_styles, &items)),
+                    *strict,
+                )
+                .await?;
 
                 let ret = db::upsert_many_builds(&mut pg_conn, new_builds).await?;
                 info!("[{}] inserted builds: {ret}", &item.value);
+                let old_version = old_versions.get(&source).map(|v| v.version.clone());
+                if old_version.as_ref() != Some(&source_version) {
+                    events.push(WebhookEvent::SourceUpdated {
+                        source: source.clone(),
+                        old_version,
+                        new_version: source_version.clone(),
+                        champions: ret as i64,
+                    });
+                }
+                versions.insert(source, source_version);
             }
 
             db::insert_log(&mut pg_conn, String::from("sync_builds")).await?;
+            notify_sync(
+                &mut pg_conn,
+                SyncEvent {
+                    action: String::from("sync_builds"),
+                    sources: versions.keys().cloned().collect(),
+                    versions,
+                },
+            )
+            .await;
+            deliver_webhooks(pool, events).await?;
+
+            Ok(())
+        }
+        Some(Commands::Load {
+            source,
+            path,
+            strict,
+            allow_unknown,
+        }) => {
+            info!("[{source}] started load from {path}");
+            if !service::is_identifier(source) {
+                anyhow::bail!("invalid source {source:?}, expected letters, digits, `-` and `_`");
+            }
+            let known = db::list_sources(pool.clone())
+                .await?
+                .iter()
+                .any(|s| &s.source == source);
+            if !known && !allow_unknown {
+                anyhow::bail!("{source} is not a known source, run sync-source or import first, or pass --allow-unknown");
+            }
+
+            let package_dir = if is_tarball(path) {
+                let output_dir = prepare_output_dir(source)?;
+                service::extract_tgz(path, &output_dir)?;
+                info!("[{source}] extracted {path}");
+                format!("{output_dir}/package")
+            } else {
+                path.clone()
+            };
+            let version = service::read_local_package_version(&package_dir).await?;
+            info!("[{source}] package version: {version}");
+
+            // only what `sync-ddragon` stored is used, so loading works offline
+            let runes = db::find_latest_ddragon_file(
+                pool.clone(),
+                String::from("runesReforged.json"),
+                service::DEFAULT_LOCALE.to_string(),
+            )
+            .await?;
+            let items = db::find_latest_ddragon_file(
+                pool.clone(),
+                String::from("item.json"),
+                service::DEFAULT_LOCALE.to_string(),
+            )
+            .await?;
+            let patch = match (runes, items) {
+                (Some(runes), Some(items)) => {
+                    info!("[{source}] validating against patch {}", items.version);
+                    Some((
+                        serde_json::from_value::<Vec<service::RuneStyle>>(runes.content)?,
+                        serde_json::from_value::<service::ItemMapResp>(items.content)?,
+                    ))
+                }
+                _ if *strict => anyhow::bail!(
+                    "no Data Dragon files synced to validate against, run sync-ddragon first"
+                ),
+                _ => {
+                    warn!("[{source}] no Data Dragon files synced, builds are not validated");
+                    None
+                }
+            };
+
+            if !known {
+                warn!("[{source}] not a known source, its builds are served but it isn't listed until sync-source or import adds it");
+            }
+
+            let new_builds = read_package_builds(
+                source,
+                &version,
+                &package_dir,
+                patch.as_ref().map(|(r, i)| (r.as_slice(), i)),
+                *strict,
+            )
+            .await?;
+            let ret = db::upsert_many_builds(&mut pg_conn, new_builds).await?;
+            info!("[{source}] inserted builds: {ret}");
+
+            db::insert_log(&mut pg_conn, String::from("sync_builds")).await?;
+            notify_sync(
+                &mut pg_conn,
+                SyncEvent {
+                    action: String::from("sync_builds"),
+                    sources: vec![source.clone()],
+                    versions: BTreeMap::from([(source.clone(), version)]),
+                },
+            )
+            .await;
+
+            Ok(())
+        }
+        Some(Commands::Prune {
+            grace_days,
+            dry_run,
+        }) => {
+            info!("started prune, grace period: {grace_days} days, dry run: {dry_run}");
+            let source_list = service::list_sources().await?;
+            let known = db::list_sources(pool.clone()).await?;
+            let missing = missing_sources(&known, &source_list);
+            let retiring = retiring_sources(&known, &missing);
+            let returned = returned_sources(&known, &source_list);
+
+            let expired = expired_sources(&known, &missing, *grace_days, SystemTime::now());
+
+            for source in retiring.iter() {
+                info!("[{source}] gone from the source list, retiring");
+            }
+            for source in returned.iter() {
+                info!("[{source}] back in the source list, no longer retired");
+            }
+            for source in expired.iter() {
+                let builds = db::count_builds_by_source(&mut pg_conn, source.clone()).await?;
+                if builds > 0 {
+                    info!(
+                        "[{source}] retired for over {grace_days} days, deleting builds: {builds}"
+                    );
+                }
+            }
+            if *dry_run {
+                info!("dry run, nothing changed");
+                return Ok(());
+            }
+
+            if !retiring.is_empty() || !returned.is_empty() {
+                let retired = db::retire_sources(&mut pg_conn, retiring.clone()).await?;
+                let unretired = db::unretire_sources(&mut pg_conn, returned.clone()).await?;
+                info!("retired: {retired}, unretired: {unretired}");
+                db::insert_log(&mut pg_conn, String::from("sync_sources")).await?;
+                notify_sync(
+                    &mut pg_conn,
+                    SyncEvent {
+                        action: String::from("sync_sources"),
+                        sources: [retiring, returned].concat(),
+                        versions: BTreeMap::new(),
+                    },
+                )
+                .await;
+            }
+            if !expired.is_empty() {
+                let deleted = db::delete_builds_by_sources(&mut pg_conn, expired.clone()).await?;
+                if deleted > 0 {
+                    info!("deleted builds: {deleted}");
+                    db::insert_log(&mut pg_conn, String::from("sync_builds")).await?;
+                    notify_sync(
+                        &mut pg_conn,
+                        SyncEvent {
+                            action: String::from("sync_builds"),
+                            sources: expired,
+                            versions: BTreeMap::new(),
+                        },
+                    )
+                    .await;
+                }
+            }
+
+            Ok(())
+        }
+        Some(Commands::Diff { .. }) => unreachable!("handled before connecting"),
+        Some(Commands::ExportItemsets { source, out }) => {
+            info!("[{source}] started export item sets to {out}");
+            let rows = db::list_builds_by_source(&mut pg_conn, source.clone()).await?;
+
+            let mut total = 0;
+            for row in rows {
+                let builds = match serde_json::from_value::<Vec<service::Build>>(row.content) {
+                    Ok(b) => b,
+                    Err(e) => {
+                        warn!("[{source}] invalid builds of {}: {}", row.champion_alias, e);
+                        continue;
+                    }
+                };
+                let files = service::itemset::item_set_files(source, &builds);
+                service::itemset::write_item_sets(out, &files)?;
+                total += files.len();
+            }
+            info!("[{source}] exported item sets: {total}");
+
+            Ok(())
+        }
+        Some(Commands::Export { format, out }) => {
+            info!("started export to {out}");
+            let sources = db::list_sources(pool)
+                .await?
+                .into_iter()
+                .map(|s| DumpedSource {
+                    name: s.name,
+                    source: s.source,
+                    version: s.version,
+                    is_aram: s.is_aram,
+                    is_urf: s.is_urf,
+                    retired_at: s.retired_at.map(Into::into),
+                })
+                .collect::<Vec<DumpedSource>>();
+            let builds = db::list_builds(&mut pg_conn)
+                .await?
+                .into_iter()
+                .map(|b| DumpedBuild {
+                    source: b.source,
+                    version: b.version,
+                    champion_alias: b.champion_alias,
+                    champion_id: b.champion_id,
+                    content: b.content,
+                })
+                .collect::<Vec<DumpedBuild>>();
+            let logs = db::list_logs(&mut pg_conn)
+                .await?
+                .into_iter()
+                .map(|l| DumpedLog {
+                    action: l.action,
+                    created_at: l.created_at.into(),
+                })
+                .collect::<Vec<DumpedLog>>();
+            info!(
+                "exporting sources: {}, builds: {}, logs: {}",
+                sources.len(),
+                builds.len(),
+                logs.len()
+            );
+
+            let dump = Dump::new(sources, builds, logs);
+            dump::write(&dump, *format, out)?;
+            info!("exported to {out}");
+
+            Ok(())
+        }
+        Some(Commands::Import { path, replace }) => {
+            info!("started import from {path}, replace: {replace}");
+            let dump = dump::read(path)?;
+            info!(
+                "read dump version {} exported at {}, sources: {}, builds: {}, logs: {}",
+                dump.header.version,
+                dump.header.exported_at,
+                dump.sources.len(),
+                dump.builds.len(),
+                dump.logs.len()
+            );
+
+            // with `--replace` the existing sources are about to be deleted
+            let known_sources = if *replace {
+                HashSet::new()
+            } else {
+                db::list_sources(pool)
+                    .await?
+                    .into_iter()
+                    .map(|s| s.source)
+                    .collect()
+            };
+            let errors = dump.validate(&known_sources);
+            if !errors.is_empty() {
+                for e in errors.iter() {
+                    error!("{e}");
+                }
+                anyhow::bail!(
+                    "{} problems found in {path}, nothing imported",
+                    errors.len()
+                );
+            }
+
+            // a source is at the version it was dumped with, not that of whichever build comes first
+            let versions = dump
+                .sources
+                .iter()
+                .map(|s| (s.source.clone(), s.version.clone()))
+                .collect::<BTreeMap<String, String>>();
+            let sources = dump
+                .sources
+                .into_iter()
+                .map(|s| db::models::NewSource {
+                    name: s.name,
+                    source: s.source,
+                    version: s.version,
+                    is_aram: s.is_aram,
+                    is_urf: s.is_urf,
+                    retired_at: s.retired_at.map(Into::into),
+                })
+                .collect();
+            let builds = dump
+                .builds
+                .into_iter()
+                .map(|b| NewBuild {
+                    source: b.source,
+                    version: b.version,
+                    champion_alias: b.champion_alias,
+                    champion_id: b.champion_id,
+                    content: b.content,
+                })
+                .collect();
+            let logs = dump
+                .logs
+                .into_iter()
+                .map(|l| db::models::NewLog {
+                    action: l.action,
+                    created_at: l.created_at.into(),
+                })
+                .collect();
+            let (sources, builds, logs) =
+                db::import_data(&mut pg_conn, sources, builds, logs, *replace).await?;
+            info!("imported sources: {sources}, builds: {builds}, logs: {logs}");
+
+            // builds changed just like after a sync, so running servers refresh
+            db::insert_log(&mut pg_conn, String::from("sync_builds")).await?;
+            notify_sync(
+                &mut pg_conn,
+                SyncEvent {
+                    action: String::from("sync_builds"),
+                    sources: versions.keys().cloned().collect(),
+                    versions,
+                },
+            )
+            .await;
 
             Ok(())
         }
+        Some(Commands::Webhook { command }) => match command {
+            WebhookCommands::Add {
+                url,
+                secret,
+                events,
+            } => {
+                service::webhook::validate(url, events).map_err(anyhow::Error::msg)?;
+                let webhook = db::insert_webhook(
+                    pool,
+                    db::models::NewWebhook {
+                        url: url.clone(),
+                        secret: secret.clone(),
+                        events: events.clone(),
+                    },
+                )
+                .await?;
+                info!("added webhook {}: {}", webhook.id, webhook.url);
+                Ok(())
+            }
+            WebhookCommands::List => {
+                for webhook in db::list_webhooks(pool).await? {
+                    let events = if webhook.events.is_empty() {
+                        String::from("all events")
+                    } else {
+                        webhook.events.join(", ")
+                    };
+                    let signed = if webhook.secret.is_some() {
+                        ", signed"
+                    } else {
+                        ""
+                    };
+                    println!("{}\t{}\t{events}{signed}", webhook.id, webhook.url);
+                }
+                Ok(())
+            }
+            WebhookCommands::Remove { id } => {
+                if db::delete_webhook(pool, *id).await? {
+                    info!("removed webhook {id}");
+                } else {
+                    error!("webhook {id} not found");
+                }
+                Ok(())
+            }
+            WebhookCommands::Test { id } => {
+                let webhook = db::list_webhooks(pool.clone())
+                    .await?
+                    .into_iter()
+                    .find(|w| w.id == *id)
+                    .ok_or_else(|| anyhow::anyhow!("webhook {id} not found"))?;
+                deliver(&pool, &webhook, &WebhookEvent::Ping).await?;
+                Ok(())
+            }
+            WebhookCommands::Deliveries { id, limit } => {
+                for d in db::list_webhook_deliveries(pool, *id, *limit).await? {
+                    let at = chrono::DateTime::<chrono::Utc>::from(d.created_at);
+                    let status = d
+                        .status_code
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| String::from("-"));
+                    let error = d.error.unwrap_or_default();
+                    println!(
+                        "{}\t{}\t{}\t{status}\t{} attempts\t{error}",
+                        d.id,
+                        at.to_rfc3339(),
+                        d.event,
+                        d.attempts
+                    );
+                }
+                Ok(())
+            }
+        },
         _ => {
             info!("no command found");
             Ok(())
         }
     }
 }
+
+async fn diff(source: &str, champion: &str, from: &str, to: &str) -> anyhow::Result<()> {
+    log::info!("[{source}] diff {champion} from {from} to {to}");
+    if !service::is_identifier(source) || !service::is_identifier(champion) {
+        anyhow::bail!("invalid source {source} or champion {champion}");
+    }
+    if !service::is_package_version(from) || !service::is_package_version(to) {
+        anyhow::bail!("invalid version {from} or {to}, expected e.g. 1.2.3");
+    }
+
+    let (from_builds, to_builds) = tokio::try_join!(
+        service::get_champion_build(champion.to_string(), source.to_string(), from.to_string()),
+        service::get_champion_build(champion.to_string(), source.to_string(), to.to_string()),
+    )?;
+    let (Some(from_builds), Some(to_builds)) = (from_builds, to_builds) else {
+        anyhow::bail!("[{source}] no builds of {champion} in {from} or {to}");
+    };
+    let diff = service::diff::diff_builds(&from_builds, &to_builds);
+    println!("{}", serde_json::to_string_pretty(&diff)?);
+
+    Ok(())
+}
+
+/// Sources, retired or not, that are no longer in the source list.
+/// An empty list is taken as upstream having a problem rather than every source being gone.
+fn missing_sources(known: &[db::models::Source], source_list: &[service::Source]) -> Vec<String> {
+    if source_list.is_empty() {
+        log::warn!("the source list is empty, no source is retired");
+        return vec![];
+    }
+
+    known
+        .iter()
+        .filter(|s| !source_list.iter().any(|item| item.value == s.source))
+        .map(|s| s.source.clone())
+        .collect()
+}
+
+/// Missing sources that aren't retired yet, missing sources stay missing on later runs.
+fn retiring_sources(known: &[db::models::Source], missing: &[String]) -> Vec<String> {
+    known
+        .iter()
+        .filter(|s| s.retired_at.is_none() && missing.contains(&s.source))
+        .map(|s| s.source.clone())
+        .collect()
+}
+
+/// Missing sources retired for over `grace_days`, a source retired by this run counts from `now`.
+/// A grace period too long to subtract from `now` expires nothing.
+fn expired_sources(
+    known: &[db::models::Source],
+    missing: &[String],
+    grace_days: u64,
+    now: SystemTime,
+) -> Vec<String> {
+    let cutoff = grace_days
+        .checked_mul(24 * 60 * 60)
+        .and_then(|secs| now.checked_sub(Duration::from_secs(secs)));
+    let Some(cutoff) = cutoff else {
+        return vec![];
+    };
+
+    known
+        .iter()
+        .filter(|s| missing.contains(&s.source) && s.retired_at.unwrap_or(now) <= cutoff)
+        .map(|s| s.source.clone())
+        .collect()
+}
+
+/// Retired sources that are back in the source list.
+fn returned_sources(known: &[db::models::Source], source_list: &[service::Source]) -> Vec<String> {
+    known
+        .iter()
+        .filter(|s| s.retired_at.is_some() && source_list.iter().any(|item| item.value == s.source))
+        .map(|s| s.source.clone())
+        .collect()
+}
+
+/// A package packed by `npm pack` rather than a directory.
+fn is_tarball(path: &str) -> bool {
+    path.ends_with(".tgz") || path.ends_with(".tar.gz")
+}
+
+/// Empties `./output/<source>`, where a package is extracted before its builds are read.
+/// Fails for a source that isn't an identifier, which could point anywhere, e.g. `..`.
+fn prepare_output_dir(source: &str) -> anyhow::Result<String> {
+    if !service::is_identifier(source) {
+        anyhow::bail!("invalid source {source:?}, expected letters, digits, `-` and `_`");
+    }
+    let output_dir = format!("./output/{source}");
+    let output_path = Path::new(&output_dir);
+    if output_path.exists() {
+        match fs::remove_dir_all(output_path) {
+            Ok(_) => log::info!("removed {output_dir}"),
+            Err(e) => log::error!("Error removing {output_dir}: {}", e),
+        }
+    }
+    if let Err(e) = fs::create_dir_all(output_path) {
+        log::error!("create {output_dir} failed: {}", e);
+    };
+    Ok(output_dir)
+}
+
+/// Reads every champion file of an extracted package, checking each build against the patch when given.
+/// With `strict`, builds that don't pass are dropped.
+async fn read_package_builds(
+    source: &str,
+    version: &str,
+    package_dir: &str,
+    patch: Option<(&[service::RuneStyle], &service::ItemMapResp)>,
+    strict: bool,
+) -> anyhow::Result<Vec<NewBuild>> {
+    let files = service::read_from_local_folder(package_dir).await?;
+    let new_builds = files
+        .into_iter()
+        .filter_map(|builds| {
+            let builds = match patch {
+                Some((rune_styles, items)) => service::validate::keep_valid_builds(
+                    builds,
+                    rune_styles,
+                    items,
+                    strict,
+                    |b, w| {
+                        log::warn!("[{}] {} {}: {}", source, &b.alias, &b.position, w);
+                    },
+                ),
+                None => builds,
+            };
+            let first_build = builds.first()?;
+            Some(NewBuild {
+                source: source.to_string(),
+                version: version.to_string(),
+                champion_id: first_build.id.clone(),
+                champion_alias: first_build.alias.clone(),
+                content: to_value(&builds).unwrap(),
+            })
+        })
+        .collect();
+
+    Ok(new_builds)
+}
+
+/// Lets a running server know about the sync, it still catches up by polling if this fails.
+async fn notify_sync(conn: &mut db::AsyncPgConnection, event: SyncEvent) {
+    if let Err(e) = db::notify_sync(conn, &event).await {
+        log::warn!("notify {} failed: {}", event.action, e);
+    }
+}
+
+/// Delivers each event to the webhooks that want it, recording how every delivery went.
+async fn deliver_webhooks(pool: db::DbPool, events: Vec<WebhookEvent>) -> anyhow::Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let webhooks = db::list_webhooks(pool.clone()).await?;
+    for event in events.iter() {
+        for webhook in webhooks.iter().filter(|w| w.wants(event.name())) {
+            deliver(&pool, webhook, event).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn deliver(
+    pool: &db::DbPool,
+    webhook: &db::models::Webhook,
+    event: &WebhookEvent,
+) -> anyhow::Result<()> {
+    let result = service::webhook::deliver(&webhook.url, webhook.secret.as_deref(), event).await;
+    if result.is_success() {
+        log::info!("delivered {} to webhook {}", event.name(), webhook.id);
+    } else {
+        log::error!(
+            "failed to deliver {} to webhook {} after {} attempts: {}",
+            event.name(),
+            webhook.id,
+            result.attempts,
+            result.error.as_deref().unwrap_or_default()
+        );
+    }
+
+    db::insert_webhook_delivery(
+        pool.clone(),
+        db::models::NewWebhookDelivery {
+            webhook_id: webhook.id,
+            event: event.name().to_string(),
+            payload: to_value(event)?,
+            status_code: result.status_code.map(i32::from),
+            error: result.error,
+            attempts: result.attempts as i32,
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    fn build(alias: &str, id: &str, position: &str) -> service::Build {
+        service::Build {
+            alias: alias.to_string(),
+            id: id.to_string(),
+            position: position.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A package dir like an extracted `npm pack`, removed by the caller.
+    fn package(name: &str, files: &[(&str, Value)]) -> String {
+        let dir = std::env::temp_dir().join(format!("quicklook-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("package.json"),
+            r#"{"name":"@x/op-gg","version":"1.2.0"}"#,
+        )
+        .unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content.to_string()).unwrap();
+        }
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn tells_tarballs_from_dirs() {
+        assert!(is_tarball("op-gg-1.2.0.tgz"));
+        assert!(is_tarball("./op-gg.tar.gz"));
+        assert!(!is_tarball("./op-gg"));
+        assert!(!is_tarball("op-gg.tar"));
+    }
+
+    #[test]
+    fn output_dir_is_only_for_identifiers() {
+        for source in ["..", ".", "a/b", "a\\b", ""] {
+            assert!(prepare_output_dir(source).is_err(), "{source}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_a_build_per_champion_file() {
+        let dir = package(
+            "load",
+            &[
+                (
+                    "Ahri.json",
+                    to_value(vec![
+                        build("Ahri", "103", "mid"),
+                        build("Ahri", "103", "top"),
+                    ])
+                    .unwrap(),
+                ),
+                (
+                    "Annie.json",
+                    to_value(vec![build("Annie", "1", "mid")]).unwrap(),
+                ),
+                ("broken.json", Value::from("not builds")),
+            ],
+        );
+        let version = service::read_local_package_version(&dir).await;
+        let builds = read_package_builds("op-gg", "1.2.0", &dir, None, true).await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(version.unwrap(), "1.2.0");
+        let mut builds = builds.unwrap();
+        builds.sort_by(|a, b| a.champion_alias.cmp(&b.champion_alias));
+        let champions = builds
+            .iter()
+            .map(|b| (b.champion_alias.as_str(), b.champion_id.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        assert_eq!(champions, [("Ahri", "103"), ("Annie", "1")]);
+        assert!(builds
+            .iter()
+            .all(|b| b.source == "op-gg" && b.version == "1.2.0"));
+        assert_eq!(builds[0].content.as_array().map(Vec::len), Some(2));
+    }
+
+    #[tokio::test]
+    async fn strict_skips_champions_without_valid_builds() {
+        // a rune page of no known style is invalid against any patch
+        let mut ahri = build("Ahri", "103", "mid");
+        ahri.runes.push(service::Rune::default());
+        let dir = package("strict", &[("Ahri.json", to_value(vec![ahri]).unwrap())]);
+        let (rune_styles, items) = (vec![], service::ItemMapResp::default());
+
+        let lenient =
+            read_package_builds("op-gg", "1.2.0", &dir, Some((&rune_styles, &items)), false).await;
+        let strict =
+            read_package_builds("op-gg", "1.2.0", &dir, Some((&rune_styles, &items)), true).await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(lenient.unwrap().len(), 1);
+        assert!(strict.unwrap().is_empty());
+    }
+
+    fn known(name: &str, retired_days_ago: Option<u64>, now: SystemTime) -> db::models::Source {
+        db::models::Source {
+            id: 0,
+            name: name.to_string(),
+            source: name.to_string(),
+            version: String::from("1.0.0"),
+            retired_at: retired_days_ago.map(|d| now - Duration::from_secs(d * 24 * 60 * 60)),
+            is_aram: false,
+            is_urf: false,
+        }
+    }
+
+    fn listed(name: &str) -> service::Source {
+        service::Source {
+            label: name.to_string(),
+            value: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn retires_missing_sources_once_and_returns_listed_ones() {
+        let now = SystemTime::now();
+        let known = [
+            known("op-gg", None, now),
+            known("gone", None, now),
+            known("long-gone", Some(3), now),
+            known("back", Some(3), now),
+        ];
+        let source_list = [listed("op-gg"), listed("back")];
+
+        let missing = missing_sources(&known, &source_list);
+        assert_eq!(missing, ["gone", "long-gone"]);
+        assert_eq!(retiring_sources(&known, &missing), ["gone"]);
+        assert_eq!(returned_sources(&known, &source_list), ["back"]);
+
+        // an empty list retires nothing and brings nothing back
+        assert!(missing_sources(&known, &[]).is_empty());
+        assert!(returned_sources(&known, &[]).is_empty());
+    }
+
+    #[test]
+    fn expires_only_missing_sources_past_the_grace_period() {
+        let now = SystemTime::now();
+        let known = [
+            known("gone", None, now),
+            known("long-gone", Some(3), now),
+            known("back", Some(3), now),
+        ];
+        let missing = [String::from("gone"), String::from("long-gone")];
+
+        assert_eq!(expired_sources(&known, &missing, 2, now), ["long-gone"]);
+        assert_eq!(
+            expired_sources(&known, &missing, 0, now),
+            ["gone", "long-gone"]
+        );
+        assert!(expired_sources(&known, &missing, 7, now).is_empty());
+        assert!(expired_sources(&known, &missing, u64::MAX, now).is_empty());
+        assert!(expired_sources(&known, &missing, u64::MAX / (24 * 60 * 60), now).is_empty());
+    }
+}
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE ddragon_files (
+    id SERIAL PRIMARY KEY,
+    version VARCHAR NOT NULL,
+    file VARCHAR NOT NULL,
+    content json NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE ddragon_files ADD UNIQUE (version, file);
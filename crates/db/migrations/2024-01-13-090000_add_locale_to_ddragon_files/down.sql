@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM ddragon_files WHERE locale <> 'en_US';
+
+ALTER TABLE ddragon_files DROP CONSTRAINT ddragon_files_version_locale_file_key;
+ALTER TABLE ddragon_files ADD UNIQUE (version, file);
+
+ALTER TABLE ddragon_files DROP COLUMN locale;
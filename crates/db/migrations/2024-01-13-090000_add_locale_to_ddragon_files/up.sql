@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE ddragon_files ADD COLUMN locale VARCHAR NOT NULL DEFAULT 'en_US';
+
+ALTER TABLE ddragon_files DROP CONSTRAINT ddragon_files_version_file_key;
+ALTER TABLE ddragon_files ADD UNIQUE (version, locale, file);
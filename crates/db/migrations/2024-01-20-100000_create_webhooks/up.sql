@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    url VARCHAR NOT NULL,
+    secret VARCHAR,
+    events TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+    event VARCHAR NOT NULL,
+    payload json NOT NULL,
+    status_code INTEGER,
+    error TEXT,
+    attempts INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX webhook_deliveries_webhook_id_idx ON webhook_deliveries (webhook_id);
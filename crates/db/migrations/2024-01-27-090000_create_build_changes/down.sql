@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER builds_record_change ON builds;
+DROP FUNCTION record_build_change();
+DROP TABLE build_changes;
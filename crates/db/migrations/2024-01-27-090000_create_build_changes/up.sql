@@ -0,0 +1,43 @@
+-- Your SQL goes here
+CREATE TABLE build_changes (
+    seq BIGSERIAL PRIMARY KEY,
+    source VARCHAR NOT NULL,
+    champion_alias VARCHAR NOT NULL,
+    champion_id VARCHAR NOT NULL,
+    kind VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- every build that exists before changes are tracked counts as added
+INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+SELECT source, champion_alias, champion_id, 'added' FROM builds ORDER BY id;
+
+CREATE FUNCTION record_build_change() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+        VALUES (OLD.source, OLD.champion_alias, OLD.champion_id, 'deleted');
+        RETURN OLD;
+    END IF;
+
+    -- syncs upsert every build, only record the ones that actually changed
+    IF TG_OP = 'UPDATE'
+        AND OLD.version = NEW.version
+        AND OLD.content::text = NEW.content::text THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+    VALUES (
+        NEW.source,
+        NEW.champion_alias,
+        NEW.champion_id,
+        CASE WHEN TG_OP = 'INSERT' THEN 'added' ELSE 'updated' END
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER builds_record_change
+AFTER INSERT OR UPDATE OR DELETE ON builds
+FOR EACH ROW EXECUTE FUNCTION record_build_change();
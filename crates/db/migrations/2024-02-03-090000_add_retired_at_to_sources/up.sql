@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE sources ADD COLUMN retired_at TIMESTAMP;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE sources DROP COLUMN is_urf;
+ALTER TABLE sources DROP COLUMN is_aram;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE sources ADD COLUMN is_aram BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE sources ADD COLUMN is_urf BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,26 @@
+-- This file should undo anything in `up.sql`
+CREATE OR REPLACE FUNCTION record_build_change() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+        VALUES (OLD.source, OLD.champion_alias, OLD.champion_id, 'deleted');
+        RETURN OLD;
+    END IF;
+
+    -- syncs upsert every build, only record the ones that actually changed
+    IF TG_OP = 'UPDATE'
+        AND OLD.version = NEW.version
+        AND OLD.content::text = NEW.content::text THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+    VALUES (
+        NEW.source,
+        NEW.champion_alias,
+        NEW.champion_id,
+        CASE WHEN TG_OP = 'INSERT' THEN 'added' ELSE 'updated' END
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,30 @@
+-- Your SQL goes here
+CREATE OR REPLACE FUNCTION record_build_change() RETURNS TRIGGER AS $$
+BEGIN
+    -- syncs upsert every build, only record the ones that actually changed
+    IF TG_OP = 'UPDATE'
+        AND OLD.version = NEW.version
+        AND OLD.content::text = NEW.content::text THEN
+        RETURN NEW;
+    END IF;
+
+    -- a seq is taken before its transaction commits, so without the lock a reader could see a
+    -- later seq commit first and move its cursor past an earlier one still in flight
+    PERFORM pg_advisory_xact_lock(hashtext('build_changes'));
+
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+        VALUES (OLD.source, OLD.champion_alias, OLD.champion_id, 'deleted');
+        RETURN OLD;
+    END IF;
+
+    INSERT INTO build_changes (source, champion_alias, champion_id, kind)
+    VALUES (
+        NEW.source,
+        NEW.champion_alias,
+        NEW.champion_id,
+        CASE WHEN TG_OP = 'INSERT' THEN 'added' ELSE 'updated' END
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -2,6 +2,7 @@ pub mod models;
 pub mod schema;
 
 use std::env;
+use std::time::SystemTime;
 
 use diesel::prelude::*;
 use diesel::upsert::excluded;
@@ -10,11 +11,18 @@ use diesel_async::{
         deadpool::{BuildError, Object, Pool},
         AsyncDieselConnectionManager,
     },
-    AsyncConnection, AsyncPgConnection, RunQueryDsl,
+    scoped_futures::ScopedFutureExt,
+    AsyncConnection, RunQueryDsl,
 };
 use dotenvy::dotenv;
 
-use models::{Build, NewSource, Source, Log};
+pub use diesel_async::AsyncPgConnection;
+
+use models::{
+    Build, BuildChange, DataDragonFile, Log, NewDataDragonFile, NewLog, NewSource, NewWebhook,
+    NewWebhookDelivery, Source, SourceOfficialVersion, SourceVersion, SyncEvent, Webhook,
+    WebhookDelivery,
+};
 
 use crate::models::NewBuild;
 
@@ -37,6 +45,7 @@ pub async fn insert_source(
         name,
         source,
         version,
+        ..Default::default()
     };
     diesel::insert_into(sources::table)
         .values(&new_source)
@@ -58,6 +67,10 @@ pub async fn insert_many_sources(
         .set((
             sources_dsl::name.eq(excluded(sources_dsl::name)),
             sources_dsl::version.eq(excluded(sources_dsl::version)),
+            sources_dsl::is_aram.eq(excluded(sources_dsl::is_aram)),
+            sources_dsl::is_urf.eq(excluded(sources_dsl::is_urf)),
+            // back in the source list, unless it's imported as retired
+            sources_dsl::retired_at.eq(excluded(sources_dsl::retired_at)),
         ))
         .execute(conn)
         .await
@@ -109,12 +122,100 @@ pub async fn upsert_many_builds(
         .await
 }
 
+pub async fn list_builds_by_source(
+    conn: &mut AsyncPgConnection,
+    src: String,
+) -> Result<Vec<Build>, diesel::result::Error> {
+    use schema::builds::dsl::*;
+
+    builds
+        .filter(source.eq(src))
+        .order(champion_alias.asc())
+        .load::<Build>(conn)
+        .await
+}
+
+pub async fn count_builds_by_source(
+    conn: &mut AsyncPgConnection,
+    src: String,
+) -> Result<i64, diesel::result::Error> {
+    use schema::builds::dsl::*;
+
+    builds
+        .filter(source.eq(src))
+        .count()
+        .get_result::<i64>(conn)
+        .await
+}
+
+pub async fn list_builds(conn: &mut AsyncPgConnection) -> Result<Vec<Build>, diesel::result::Error> {
+    use schema::builds::dsl::*;
+
+    builds
+        .order((source.asc(), champion_alias.asc()))
+        .load::<Build>(conn)
+        .await
+}
+
 pub fn get_db_config() -> AsyncDieselConnectionManager<diesel_async::AsyncPgConnection> {
     let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
     AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new(db_url)
 }
 
+/// Channel the CLI notifies with a `SyncEvent` after each sync.
+pub const SYNC_CHANNEL: &str = "quicklook_sync";
+
+pub async fn notify_sync(
+    conn: &mut AsyncPgConnection,
+    event: &SyncEvent,
+) -> anyhow::Result<()> {
+    use diesel::sql_types::Text;
+
+    let payload = serde_json::to_string(event)?;
+    diesel::sql_query("SELECT pg_notify($1, $2)")
+        .bind::<Text, _>(SYNC_CHANNEL)
+        .bind::<Text, _>(payload)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Listens on `SYNC_CHANNEL` on a dedicated connection, calling `on_event` for each sync
+/// until the connection is lost. A payload that isn't a `SyncEvent` is passed on as an error
+/// rather than dropping the connection.
+pub async fn listen_syncs(mut on_event: impl FnMut(anyhow::Result<SyncEvent>)) -> anyhow::Result<()> {
+    use tokio_postgres::AsyncMessage;
+
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let (client, mut connection) = tokio_postgres::connect(&db_url, tokio_postgres::NoTls).await?;
+
+    // the connection does its IO, notifications included, only while polled
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(message) = std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+            if tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    client
+        .batch_execute(&format!("LISTEN {SYNC_CHANNEL}"))
+        .await?;
+
+    while let Some(message) = rx.recv().await {
+        match message? {
+            AsyncMessage::Notification(n) => {
+                let event = serde_json::from_str::<SyncEvent>(n.payload())
+                    .map_err(|e| anyhow::anyhow!("invalid {SYNC_CHANNEL} payload {}: {e}", n.payload()));
+                on_event(event)
+            }
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
 pub type DbPool = Pool<AsyncPgConnection>;
 
 pub fn make_db_pool() -> Result<DbPool, BuildError> {
@@ -137,6 +238,54 @@ pub async fn list_sources(pool: DbPool) -> anyhow::Result<Vec<Source>> {
     Ok(result)
 }
 
+/// Sources still in the source list.
+pub async fn list_active_sources(pool: DbPool) -> anyhow::Result<Vec<Source>> {
+    use schema::sources::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = sources
+        .filter(retired_at.is_null())
+        .load::<Source>(&mut conn)
+        .await?;
+    Ok(result)
+}
+
+/// Marks sources as gone from the source list, keeping when it first happened.
+pub async fn retire_sources(
+    conn: &mut AsyncPgConnection,
+    list: Vec<String>,
+) -> Result<usize, diesel::result::Error> {
+    use schema::sources::dsl::*;
+
+    diesel::update(sources.filter(source.eq_any(list).and(retired_at.is_null())))
+        .set(retired_at.eq(SystemTime::now()))
+        .execute(conn)
+        .await
+}
+
+pub async fn unretire_sources(
+    conn: &mut AsyncPgConnection,
+    list: Vec<String>,
+) -> Result<usize, diesel::result::Error> {
+    use schema::sources::dsl::*;
+
+    diesel::update(sources.filter(source.eq_any(list).and(retired_at.is_not_null())))
+        .set(retired_at.eq(None::<SystemTime>))
+        .execute(conn)
+        .await
+}
+
+pub async fn delete_builds_by_sources(
+    conn: &mut AsyncPgConnection,
+    list: Vec<String>,
+) -> Result<usize, diesel::result::Error> {
+    use schema::builds::dsl::*;
+
+    diesel::delete(builds.filter(source.eq_any(list)))
+        .execute(conn)
+        .await
+}
+
 pub async fn find_builds_by_champion_alias_and_source(
     pool: DbPool,
     champ: String,
@@ -167,6 +316,162 @@ pub async fn find_builds_by_champion_id_and_source(
     Ok(result)
 }
 
+pub async fn list_source_official_versions(
+    pool: DbPool,
+) -> anyhow::Result<Vec<SourceOfficialVersion>> {
+    let mut conn = get_conn(pool).await?;
+    let result = diesel::sql_query(
+        "SELECT source, content->0->>'officialVersion' AS official_version, COUNT(*) AS champions \
+         FROM builds GROUP BY 1, 2",
+    )
+    .load::<SourceOfficialVersion>(&mut conn)
+    .await?;
+    Ok(result)
+}
+
+/// Counts the builds of each package version of each source.
+pub async fn list_source_versions(pool: DbPool) -> anyhow::Result<Vec<SourceVersion>> {
+    let mut conn = get_conn(pool).await?;
+    let result = diesel::sql_query(
+        "SELECT source, version, COUNT(*) AS champions FROM builds GROUP BY 1, 2",
+    )
+    .load::<SourceVersion>(&mut conn)
+    .await?;
+    Ok(result)
+}
+
+/// The newest package version of each source that has builds.
+pub async fn latest_source_versions(
+    pool: DbPool,
+) -> anyhow::Result<std::collections::HashMap<String, SourceVersion>> {
+    let mut latest = std::collections::HashMap::<String, SourceVersion>::new();
+    for row in list_source_versions(pool).await? {
+        let newer = latest
+            .get(&row.source)
+            .is_none_or(|v| version_key(&row.version) > version_key(&v.version));
+        if newer {
+            latest.insert(row.source.clone(), row);
+        }
+    }
+    Ok(latest)
+}
+
+/// Sort key comparing versions like `13.22.1` component-wise rather than as text.
+pub fn version_key(v: &str) -> Vec<u32> {
+    v.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+/// Finds a source's build for a champion matching any of the given aliases or ids.
+pub async fn find_build_by_source_and_champion(
+    pool: DbPool,
+    src: String,
+    aliases: Vec<String>,
+    ids: Vec<String>,
+) -> anyhow::Result<Option<Build>> {
+    use schema::builds::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = builds
+        .filter(
+            source
+                .eq(src)
+                .and(champion_alias.eq_any(aliases).or(champion_id.eq_any(ids))),
+        )
+        .first::<Build>(&mut conn)
+        .await
+        .optional()?;
+    Ok(result)
+}
+
+/// Lists the builds of every source for a champion, matched by any of the given aliases or ids.
+pub async fn find_builds_by_champion(
+    pool: DbPool,
+    aliases: Vec<String>,
+    ids: Vec<String>,
+) -> anyhow::Result<Vec<Build>> {
+    use schema::builds::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = builds
+        .filter(champion_alias.eq_any(aliases).or(champion_id.eq_any(ids)))
+        .order(source.asc())
+        .load::<Build>(&mut conn)
+        .await?;
+    Ok(result)
+}
+
+/// Looks up builds for many `(source, champion)` pairs in a single query,
+/// where `champion` may be either the champion alias or the champion id.
+pub async fn find_builds_by_source_and_champion_pairs(
+    pool: DbPool,
+    pairs: Vec<(String, String)>,
+) -> anyhow::Result<Vec<Build>> {
+    use schema::builds::dsl::*;
+
+    if pairs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut query = builds.into_boxed();
+    for (src, champ) in pairs {
+        query = query.or_filter(
+            source
+                .eq(src)
+                .and(champion_alias.eq(champ.clone()).or(champion_id.eq(champ))),
+        );
+    }
+
+    let mut conn = get_conn(pool).await?;
+    let result = query.load::<Build>(&mut conn).await?;
+    Ok(result)
+}
+
+/// Lists build changes after the `since` sequence number, oldest first.
+pub async fn list_build_changes(
+    pool: DbPool,
+    since: i64,
+    limit: i64,
+) -> anyhow::Result<Vec<BuildChange>> {
+    use schema::build_changes::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = build_changes
+        .filter(seq.gt(since))
+        .order(seq.asc())
+        .limit(limit)
+        .load::<BuildChange>(&mut conn)
+        .await?;
+    Ok(result)
+}
+
+/// The sequence number of the latest build change, of one source or of any, `0` before any.
+pub async fn latest_build_change_seq(pool: DbPool, src: Option<String>) -> anyhow::Result<i64> {
+    use schema::build_changes::dsl::*;
+
+    let mut query = build_changes.select(diesel::dsl::max(seq)).into_boxed();
+    if let Some(src) = src {
+        query = query.filter(source.eq(src));
+    }
+
+    let mut conn = get_conn(pool).await?;
+    let result = query.first::<Option<i64>>(&mut conn).await?;
+    Ok(result.unwrap_or(0))
+}
+
+/// Finds the latest log of any of the given actions, e.g. the last `sync_builds`.
+pub async fn find_latest_log(pool: DbPool, actions: Vec<String>) -> anyhow::Result<Option<Log>> {
+    use schema::logs::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = logs
+        .filter(action.eq_any(actions))
+        .order(id.desc())
+        .first::<Log>(&mut conn)
+        .await
+        .optional()?;
+    Ok(result)
+}
+
 pub async fn insert_log(conn: &mut AsyncPgConnection, action: String) -> Result<Log, diesel::result::Error> {
     use schema::logs::{dsl as logs_dsl, table};
 
@@ -176,3 +481,206 @@ pub async fn insert_log(conn: &mut AsyncPgConnection, action: String) -> Result<
         .get_result(conn)
         .await
 }
+
+pub async fn list_logs(conn: &mut AsyncPgConnection) -> Result<Vec<Log>, diesel::result::Error> {
+    use schema::logs::dsl::*;
+
+    logs.order(id.asc()).load::<Log>(conn).await
+}
+
+pub async fn insert_many_logs(
+    conn: &mut AsyncPgConnection,
+    list: Vec<NewLog>,
+) -> Result<usize, diesel::result::Error> {
+    use schema::logs::table;
+
+    diesel::insert_into(table).values(&list).execute(conn).await
+}
+
+/// Rows per insert, well below Postgres' limit of 65535 bind parameters.
+const INSERT_CHUNK_SIZE: usize = 1000;
+
+/// Upserts sources and builds and appends logs in one transaction, after deleting every
+/// existing source, build and log when `replace` is set.
+pub async fn import_data(
+    conn: &mut AsyncPgConnection,
+    sources: Vec<NewSource>,
+    builds: Vec<NewBuild>,
+    logs: Vec<NewLog>,
+    replace: bool,
+) -> Result<(usize, usize, usize), diesel::result::Error> {
+    conn.transaction(|conn| {
+        async move {
+            if replace {
+                diesel::delete(schema::builds::table).execute(conn).await?;
+                diesel::delete(schema::sources::table).execute(conn).await?;
+                diesel::delete(schema::logs::table).execute(conn).await?;
+            }
+
+            let mut totals = (0, 0, 0);
+            for chunk in sources.chunks(INSERT_CHUNK_SIZE) {
+                totals.0 += insert_many_sources(conn, chunk.to_vec()).await?;
+            }
+            for chunk in builds.chunks(INSERT_CHUNK_SIZE) {
+                totals.1 += upsert_many_builds(conn, chunk.to_vec()).await?;
+            }
+            for chunk in logs.chunks(INSERT_CHUNK_SIZE) {
+                totals.2 += insert_many_logs(conn, chunk.to_vec()).await?;
+            }
+            Ok(totals)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+pub async fn upsert_many_ddragon_files(
+    conn: &mut AsyncPgConnection,
+    list: Vec<NewDataDragonFile>,
+) -> Result<usize, diesel::result::Error> {
+    use schema::ddragon_files::{dsl as files_dsl, table};
+
+    diesel::insert_into(table)
+        .values(&list)
+        .on_conflict((files_dsl::version, files_dsl::locale, files_dsl::file))
+        .do_update()
+        .set(files_dsl::content.eq(excluded(files_dsl::content)))
+        .execute(conn)
+        .await
+}
+
+/// Finds a Data Dragon file, e.g. `champion.json`, of the newest synced patch.
+/// Patches can be synced in any order, so that's the highest version rather than the last row.
+pub async fn find_latest_ddragon_file(
+    pool: DbPool,
+    name: String,
+    lang: String,
+) -> anyhow::Result<Option<DataDragonFile>> {
+    use schema::ddragon_files::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let versions = ddragon_files
+        .filter(file.eq(&name).and(locale.eq(&lang)))
+        .select(version)
+        .load::<String>(&mut conn)
+        .await?;
+    let Some(latest) = versions.into_iter().max_by_key(|v| version_key(v)) else {
+        return Ok(None);
+    };
+
+    let result = ddragon_files
+        .filter(version.eq(latest).and(file.eq(name)).and(locale.eq(lang)))
+        .first::<DataDragonFile>(&mut conn)
+        .await
+        .optional()?;
+    Ok(result)
+}
+
+pub async fn find_ddragon_file(
+    pool: DbPool,
+    ver: String,
+    name: String,
+    lang: String,
+) -> anyhow::Result<Option<DataDragonFile>> {
+    use schema::ddragon_files::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = ddragon_files
+        .filter(version.eq(ver).and(file.eq(name)).and(locale.eq(lang)))
+        .first::<DataDragonFile>(&mut conn)
+        .await
+        .optional()?;
+    Ok(result)
+}
+
+/// Lists the synced patch versions, newest first.
+pub async fn list_ddragon_versions(pool: DbPool) -> anyhow::Result<Vec<String>> {
+    use schema::ddragon_files::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let mut result = ddragon_files
+        .select(version)
+        .distinct()
+        .load::<String>(&mut conn)
+        .await?;
+    result.sort_by_cached_key(|v| std::cmp::Reverse(version_key(v)));
+    Ok(result)
+}
+
+/// Lists the locales of the synced Data Dragon files.
+pub async fn list_ddragon_locales(pool: DbPool) -> anyhow::Result<Vec<String>> {
+    use schema::ddragon_files::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = ddragon_files
+        .select(locale)
+        .distinct()
+        .order(locale.asc())
+        .load::<String>(&mut conn)
+        .await?;
+    Ok(result)
+}
+
+pub async fn insert_webhook(pool: DbPool, new_webhook: NewWebhook) -> anyhow::Result<Webhook> {
+    use schema::webhooks;
+
+    let mut conn = get_conn(pool).await?;
+    let result = diesel::insert_into(webhooks::table)
+        .values(&new_webhook)
+        .returning(Webhook::as_returning())
+        .get_result(&mut conn)
+        .await?;
+    Ok(result)
+}
+
+pub async fn list_webhooks(pool: DbPool) -> anyhow::Result<Vec<Webhook>> {
+    use schema::webhooks::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = webhooks.order(id.asc()).load::<Webhook>(&mut conn).await?;
+    Ok(result)
+}
+
+/// Deletes a webhook along with its deliveries, `false` if it didn't exist.
+pub async fn delete_webhook(pool: DbPool, webhook_id: i32) -> anyhow::Result<bool> {
+    use schema::webhooks::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let deleted = diesel::delete(webhooks.filter(id.eq(webhook_id)))
+        .execute(&mut conn)
+        .await?;
+    Ok(deleted > 0)
+}
+
+pub async fn insert_webhook_delivery(
+    pool: DbPool,
+    delivery: NewWebhookDelivery,
+) -> anyhow::Result<WebhookDelivery> {
+    use schema::webhook_deliveries;
+
+    let mut conn = get_conn(pool).await?;
+    let result = diesel::insert_into(webhook_deliveries::table)
+        .values(&delivery)
+        .returning(WebhookDelivery::as_returning())
+        .get_result(&mut conn)
+        .await?;
+    Ok(result)
+}
+
+/// Lists a webhook's deliveries, latest first.
+pub async fn list_webhook_deliveries(
+    pool: DbPool,
+    hook_id: i32,
+    limit: i64,
+) -> anyhow::Result<Vec<WebhookDelivery>> {
+    use schema::webhook_deliveries::dsl::*;
+
+    let mut conn = get_conn(pool).await?;
+    let result = webhook_deliveries
+        .filter(webhook_id.eq(hook_id))
+        .order(id.desc())
+        .limit(limit)
+        .load::<WebhookDelivery>(&mut conn)
+        .await?;
+    Ok(result)
+}
@@ -13,6 +13,11 @@ pub struct Source {
     pub name: String,
     pub source: String,
     pub version: String,
+    /// set once the source is gone from the source list, its builds are deleted after a grace period
+    #[serde(default, skip_serializing)]
+    pub retired_at: Option<SystemTime>,
+    pub is_aram: bool,
+    pub is_urf: bool,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -35,6 +40,10 @@ pub struct NewSource {
     pub name: String,
     pub source: String,
     pub version: String,
+    pub is_aram: bool,
+    pub is_urf: bool,
+    /// `None` for a source in the source list, imports keep when it was retired
+    pub retired_at: Option<SystemTime>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -55,5 +64,145 @@ pub struct NewBuild {
 pub struct Log {
     pub id: i32,
     pub action: String,
-    created_at: SystemTime,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::logs)]
+pub struct NewLog {
+    pub action: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Queryable, Selectable, Identifiable)]
+#[diesel(table_name = crate::schema::ddragon_files)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DataDragonFile {
+    pub id: i32,
+    pub version: String,
+    pub file: String,
+    pub content: serde_json::Value,
+    pub created_at: SystemTime,
+    pub locale: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::ddragon_files)]
+pub struct NewDataDragonFile {
+    pub version: String,
+    pub file: String,
+    pub content: serde_json::Value,
+    pub locale: String,
+}
+
+/// A build added, updated or deleted, recorded by a trigger on `builds`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Queryable, Selectable, Identifiable)]
+#[diesel(table_name = crate::schema::build_changes)]
+#[diesel(primary_key(seq))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BuildChange {
+    pub seq: i64,
+    pub source: String,
+    pub champion_alias: String,
+    pub champion_id: String,
+    /// `added`, `updated` or `deleted`
+    pub kind: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Queryable, Selectable, Identifiable)]
+#[diesel(table_name = crate::schema::webhooks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    /// signs payloads with HMAC-SHA256, never sent back to clients
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+    /// events to deliver, all of them when empty
+    pub events: Vec<String>,
+    pub created_at: SystemTime,
+}
+
+impl Webhook {
+    pub fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::webhooks)]
+pub struct NewWebhook {
+    pub url: String,
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Queryable, Selectable, Identifiable)]
+#[diesel(table_name = crate::schema::webhook_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// the last response's status, `None` if the receiver was never reached
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub attempts: i32,
+    pub created_at: SystemTime,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub attempts: i32,
+}
+
+/// Official (League) versions of a source's builds, with how many champions are on each.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(QueryableByName)]
+pub struct SourceOfficialVersion {
+    #[diesel(sql_type = diesel::sql_types::Varchar)]
+    pub source: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub official_version: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub champions: i64,
+}
+
+/// Package versions of a source's builds, with how many champions are on each.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(QueryableByName)]
+pub struct SourceVersion {
+    #[diesel(sql_type = diesel::sql_types::Varchar)]
+    pub source: String,
+    #[diesel(sql_type = diesel::sql_types::Varchar)]
+    pub version: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub champions: i64,
+}
+
+/// Payload of the `SYNC_CHANNEL` notification sent after a sync is committed.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncEvent {
+    /// the logged action, e.g. `sync_builds`
+    pub action: String,
+    pub sources: Vec<String>,
+    /// synced version by source, or Data Dragon version by locale for `sync_ddragon`
+    pub versions: std::collections::BTreeMap<String, String>,
 }
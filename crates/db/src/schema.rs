@@ -1,5 +1,16 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    build_changes (seq) {
+        seq -> Int8,
+        source -> Varchar,
+        champion_alias -> Varchar,
+        champion_id -> Varchar,
+        kind -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     builds (id) {
         id -> Int4,
@@ -11,6 +22,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    ddragon_files (id) {
+        id -> Int4,
+        version -> Varchar,
+        file -> Varchar,
+        content -> Json,
+        created_at -> Timestamp,
+        locale -> Varchar,
+    }
+}
+
 diesel::table! {
     logs (id) {
         id -> Int4,
@@ -25,11 +47,43 @@ diesel::table! {
         name -> Varchar,
         source -> Varchar,
         version -> Varchar,
+        retired_at -> Nullable<Timestamp>,
+        is_aram -> Bool,
+        is_urf -> Bool,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Int4,
+        webhook_id -> Int4,
+        event -> Varchar,
+        payload -> Json,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        attempts -> Int4,
+        created_at -> Timestamp,
     }
 }
 
+diesel::table! {
+    webhooks (id) {
+        id -> Int4,
+        url -> Varchar,
+        secret -> Nullable<Varchar>,
+        events -> Array<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    build_changes,
     builds,
+    ddragon_files,
     logs,
     sources,
+    webhook_deliveries,
+    webhooks,
 );
@@ -0,0 +1,79 @@
+mod common;
+
+use std::time::Duration;
+
+use diesel::prelude::*;
+use diesel_async::{RunQueryDsl, SimpleAsyncConnection};
+
+#[tokio::test]
+async fn changes_become_visible_in_seq_order() {
+    let Some(pool) = common::pool() else {
+        return;
+    };
+    let source = common::unique("test-source");
+    let since = db::latest_build_change_seq(pool.clone(), None)
+        .await
+        .unwrap();
+
+    // the first upsert takes its seq, then stays uncommitted while the second one runs
+    let mut first = db::get_conn(pool.clone()).await.unwrap();
+    first.batch_execute("BEGIN").await.unwrap();
+    db::upsert_many_builds(&mut first, vec![build(&source, "Ahri", "103")])
+        .await
+        .unwrap();
+
+    let second = tokio::spawn({
+        let pool = pool.clone();
+        let source = source.clone();
+        async move {
+            let mut conn = db::get_conn(pool).await.unwrap();
+            db::upsert_many_builds(&mut conn, vec![build(&source, "Annie", "1")])
+                .await
+                .unwrap();
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // a client polling now must never see the second change without the first
+    let midway = changes_of(&pool, since, &source).await;
+    first.batch_execute("COMMIT").await.unwrap();
+    second.await.unwrap();
+    let last = changes_of(&pool, since, &source).await;
+    delete_builds(&mut first, &source).await;
+
+    assert_eq!(last, ["Ahri", "Annie"]);
+    assert!(
+        last.starts_with(&midway),
+        "{midway:?} isn't a prefix of {last:?}"
+    );
+}
+
+fn build(source: &str, alias: &str, id: &str) -> db::models::NewBuild {
+    db::models::NewBuild {
+        source: source.to_string(),
+        version: String::from("1.0.0"),
+        champion_alias: alias.to_string(),
+        champion_id: id.to_string(),
+        content: serde_json::json!([]),
+    }
+}
+
+/// The champions of `source` changed after `since`, in the order a client reads them.
+async fn changes_of(pool: &db::DbPool, since: i64, source: &str) -> Vec<String> {
+    db::list_build_changes(pool.clone(), since, i64::MAX)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|c| c.source == source)
+        .map(|c| c.champion_alias)
+        .collect()
+}
+
+async fn delete_builds(conn: &mut db::AsyncPgConnection, name: &str) {
+    use db::schema::builds::dsl::*;
+
+    diesel::delete(builds.filter(source.eq(name)))
+        .execute(conn)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,19 @@
+//! Helpers of the tests that need a database migrated with `diesel migration run`.
+
+/// A pool on `DATABASE_URL`, `None` when it isn't set so the tests are skipped.
+pub fn pool() -> Option<db::DbPool> {
+    if std::env::var("DATABASE_URL").is_err() {
+        eprintln!("DATABASE_URL is not set, skipped");
+        return None;
+    }
+    Some(db::make_db_pool().expect("DATABASE_URL is valid"))
+}
+
+/// A name no other test run uses, so tests can share a database.
+pub fn unique(prefix: &str) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    format!("{prefix}-{}-{nanos}", std::process::id())
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+#[tokio::test]
+async fn latest_file_is_of_the_newest_patch() {
+    let Some(pool) = common::pool() else {
+        return;
+    };
+    let name = common::unique("test-file") + ".json";
+    let mut conn = db::get_conn(pool.clone()).await.unwrap();
+
+    // a patch synced late, e.g. a backfill, still isn't the latest
+    for version in ["13.9.1", "13.10.1", "13.2.1"] {
+        let file = db::models::NewDataDragonFile {
+            version: version.to_string(),
+            file: name.clone(),
+            content: serde_json::json!({ "version": version }),
+            locale: String::from("en_US"),
+        };
+        db::upsert_many_ddragon_files(&mut conn, vec![file])
+            .await
+            .unwrap();
+    }
+
+    let latest = db::find_latest_ddragon_file(pool.clone(), name.clone(), String::from("en_US"))
+        .await
+        .unwrap();
+    let missing = db::find_latest_ddragon_file(pool, name.clone(), String::from("ko_KR"))
+        .await
+        .unwrap();
+    delete_files(&mut conn, &name).await;
+
+    assert_eq!(latest.map(|f| f.version).as_deref(), Some("13.10.1"));
+    assert!(missing.is_none());
+}
+
+async fn delete_files(conn: &mut db::AsyncPgConnection, name: &str) {
+    use db::schema::ddragon_files::dsl::*;
+
+    diesel::delete(ddragon_files.filter(file.eq(name)))
+        .execute(conn)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,120 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Images are far below this, it only guards against a misbehaving mirror.
+const MAX_ASSET_SIZE: u64 = 10 * 1024 * 1024;
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Proxies ddragon images, keeping a copy of each one on disk.
+#[derive(Clone)]
+pub struct AssetCache {
+    dir: PathBuf,
+    agent: ureq::Agent,
+}
+
+impl AssetCache {
+    pub fn new(dir: impl Into<PathBuf>, agent: ureq::Agent) -> Self {
+        Self {
+            dir: dir.into(),
+            agent,
+        }
+    }
+
+    /// Reads `key` from disk, downloading it from `url` on a miss.
+    /// Returns `None` when ddragon doesn't have it either.
+    pub async fn get(&self, key: &Path, url: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let path = self.dir.join(key);
+        if let Ok(bytes) = tokio::fs::read(&path).await {
+            return Ok(Some(bytes));
+        }
+
+        let agent = self.agent.clone();
+        let url = url.to_string();
+        let Some(bytes) = tokio::task::spawn_blocking(move || download(&agent, &url)).await??
+        else {
+            return Ok(None);
+        };
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // write then rename, so concurrent readers never see a partial file
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        Ok(Some(bytes))
+    }
+}
+
+/// `None` on a 404, an error when the body is over `MAX_ASSET_SIZE` rather than a truncated image.
+fn download(agent: &ureq::Agent, url: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let resp = match agent.get(url).call() {
+        Ok(resp) => resp,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    read_limited(resp.into_reader(), MAX_ASSET_SIZE)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("{url}: {e}"))
+}
+
+/// Reads one byte past `limit`, to tell a body of exactly `limit` bytes from a longer one.
+fn read_limited(reader: impl Read, limit: u64) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    reader.take(limit + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > limit {
+        anyhow::bail!("over {limit} bytes");
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn reads_up_to_the_limit() {
+        let bytes = read_limited(Cursor::new(vec![1; 16]), 16).unwrap();
+        assert_eq!(bytes.len(), 16);
+    }
+
+    #[test]
+    fn fails_past_the_limit() {
+        let e = read_limited(Cursor::new(vec![1; 17]), 16).unwrap_err();
+        assert_eq!(e.to_string(), "over 16 bytes");
+    }
+
+    #[tokio::test]
+    async fn serves_cached_assets_without_downloading() {
+        let dir = std::env::temp_dir().join(format!("quicklook-assets-{}", std::process::id()));
+        let key = Path::new("13.24.1/item/1001.png");
+        std::fs::create_dir_all(dir.join(key).parent().unwrap()).unwrap();
+        std::fs::write(dir.join(key), b"png").unwrap();
+
+        let assets = AssetCache::new(&dir, ureq::agent());
+        // nothing listens there, so only the copy on disk can answer
+        let cached = assets.get(key, "http://127.0.0.1:9/1001.png").await;
+        let missing = assets
+            .get(
+                Path::new("13.24.1/item/1002.png"),
+                "http://127.0.0.1:9/1002.png",
+            )
+            .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cached.unwrap().as_deref(), Some(&b"png"[..]));
+        assert!(missing.is_err());
+    }
+}
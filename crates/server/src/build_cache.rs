@@ -0,0 +1,221 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use serde_derive::Serialize;
+
+use crate::events::{self, SyncEvents};
+
+/// How a build was looked up. A champion is only resolved to the closest match for single
+/// builds, so the same input can find different builds by each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lookup {
+    Resolved,
+    Exact,
+}
+
+/// (lookup, source, champion as it was requested)
+type CacheKey = (Lookup, String, String);
+
+/// An LRU of builds in front of the DB, emptied whenever a `sync_builds` completes.
+#[derive(Clone)]
+pub struct BuildCache {
+    entries: Arc<Mutex<LruCache<CacheKey, (Instant, db::models::Build)>>>,
+    ttl: Duration,
+    /// bumped on every invalidation, so that builds read before it aren't cached after it
+    generation: Arc<AtomicU64>,
+    /// the last `sync_builds` seen, anything cached before it is outdated
+    last_sync: Arc<RwLock<Option<db::models::Log>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl BuildCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+            ttl,
+            generation: Default::default(),
+            last_sync: Default::default(),
+            hits: Default::default(),
+            misses: Default::default(),
+        }
+    }
+
+    pub fn get(&self, lookup: Lookup, source: &str, champion: &str) -> Option<db::models::Build> {
+        let key = (lookup, source.to_string(), champion.to_string());
+        let mut entries = self.entries.lock().unwrap();
+        let found = match entries.get(&key) {
+            Some((at, build)) if at.elapsed() < self.ttl => Some(build.clone()),
+            Some(_) => {
+                entries.pop(&key);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// To be taken before reading builds from the DB and passed to `insert`.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Skipped when the cache was invalidated since `generation`, as the build may be outdated.
+    pub fn insert(
+        &self,
+        generation: u64,
+        lookup: Lookup,
+        source: &str,
+        champion: &str,
+        build: db::models::Build,
+    ) {
+        let key = (lookup, source.to_string(), champion.to_string());
+        let mut entries = self.entries.lock().unwrap();
+        if self.generation() == generation {
+            entries.put(key, (Instant::now(), build));
+        }
+    }
+
+    pub fn invalidate(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock().unwrap();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.len(),
+            capacity: entries.cap().get(),
+        }
+    }
+
+    pub fn last_sync(&self) -> Option<db::models::Log> {
+        self.last_sync.read().unwrap().clone()
+    }
+
+    /// Records the latest `sync_builds`, invalidating the cache when it's a new one.
+    pub fn observe_sync(&self, log: Option<db::models::Log>) {
+        let mut last_sync = self.last_sync.write().unwrap();
+        let id_of = |l: &Option<db::models::Log>| l.as_ref().map(|l| l.id);
+        if id_of(&last_sync) != id_of(&log) {
+            if last_sync.is_some() {
+                tracing::info!("builds synced, invalidated build cache");
+            }
+            self.invalidate();
+            *last_sync = log;
+        }
+    }
+
+    /// Re-reads the latest `sync_builds` from the `logs` table.
+    pub async fn refresh(&self, pool: db::DbPool) {
+        match db::find_latest_log(pool, vec!["sync_builds".to_string()]).await {
+            Ok(log) => self.observe_sync(log),
+            Err(e) => tracing::warn!("failed to read syncs for build cache: {e}"),
+        }
+    }
+
+    /// Refreshes as soon as a sync is announced, and polls in case an announcement was missed.
+    pub fn watch(&self, pool: db::DbPool, events: &SyncEvents, interval: Duration) {
+        let cache = self.clone();
+        let poll_pool = pool.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                cache.refresh(poll_pool.clone()).await;
+            }
+        });
+
+        let cache = self.clone();
+        events::subscribe_with(events, move |event| {
+            let cache = cache.clone();
+            let pool = pool.clone();
+            async move {
+                if event.is_none_or(|e| e.action == "sync_builds") {
+                    cache.refresh(pool).await;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(version: &str) -> db::models::Build {
+        db::models::Build {
+            id: 1,
+            source: "op.gg".to_string(),
+            version: version.to_string(),
+            champion_alias: "Ahri".to_string(),
+            champion_id: "103".to_string(),
+            content: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn drops_builds_read_before_an_invalidation() {
+        let cache = BuildCache::new(8, Duration::from_secs(60));
+        let generation = cache.generation();
+        cache.invalidate();
+        cache.insert(
+            generation,
+            Lookup::Resolved,
+            "op.gg",
+            "ahri",
+            build("1.0.0"),
+        );
+        assert!(cache.get(Lookup::Resolved, "op.gg", "ahri").is_none());
+
+        cache.insert(
+            cache.generation(),
+            Lookup::Resolved,
+            "op.gg",
+            "ahri",
+            build("1.0.1"),
+        );
+        let cached = cache.get(Lookup::Resolved, "op.gg", "ahri");
+        assert_eq!(cached.map(|b| b.version), Some("1.0.1".to_string()));
+    }
+
+    #[test]
+    fn keeps_lookups_apart() {
+        let cache = BuildCache::new(8, Duration::from_secs(60));
+        cache.insert(
+            cache.generation(),
+            Lookup::Resolved,
+            "op.gg",
+            "ahr",
+            build("1.0.0"),
+        );
+        assert!(cache.get(Lookup::Exact, "op.gg", "ahr").is_none());
+        assert!(cache.get(Lookup::Resolved, "op.gg", "ahr").is_some());
+    }
+}
@@ -3,3 +3,67 @@ pub const NPM_REGISTRY: &str = "https://registry.npmjs.org/@champ-r";
 pub const CDN_URL: &str = "https://cdn.jsdelivr.net/npm/@champ-r";
 
 pub const DATA_DRAGON_URL: &str = "https://ddragon.leagueoflegends.com";
+
+/// `DATA_DRAGON_URL` from env, e.g. a mirror for regions where ddragon is slow or blocked.
+pub fn data_dragon_url() -> String {
+    std::env::var("DATA_DRAGON_URL").unwrap_or_else(|_| DATA_DRAGON_URL.to_string())
+}
+
+pub const DEFAULT_ASSETS_DIR: &str = "./cache/assets";
+
+pub fn assets_dir() -> String {
+    std::env::var("ASSETS_DIR").unwrap_or_else(|_| DEFAULT_ASSETS_DIR.to_string())
+}
+
+pub const DEFAULT_CACHE_CONTROL: &str = "public, max-age=300";
+
+/// `Cache-Control` of builds, sources and Data Dragon responses, overridden by `CACHE_CONTROL`.
+pub fn cache_control() -> String {
+    std::env::var("CACHE_CONTROL").unwrap_or_else(|_| DEFAULT_CACHE_CONTROL.to_string())
+}
+
+/// Assets of a patch never change, so when the patch is given they can be cached for a long time.
+pub const ASSET_MAX_AGE: u64 = 60 * 60 * 24 * 30;
+
+pub const MAX_BATCH_SIZE: usize = 50;
+
+pub const CHAMPION_SEARCH_LIMIT: usize = 10;
+
+pub const DATA_DRAGON_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// How long a failed ddragon fetch is remembered before ddragon is tried again.
+pub const DATA_DRAGON_FAILURE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Sources more patches behind than this are flagged as stale, overridden by `STALE_PATCH_THRESHOLD`.
+pub const DEFAULT_STALE_PATCH_THRESHOLD: usize = 1;
+
+pub fn stale_patch_threshold() -> usize {
+    std::env::var("STALE_PATCH_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_STALE_PATCH_THRESHOLD)
+}
+
+pub const BUILD_CACHE_CAPACITY: usize = 2000;
+
+/// Syncs invalidate the build cache, the TTL only bounds how long a missed one goes unnoticed.
+pub const BUILD_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60 * 6);
+
+/// Syncs are announced on `db::SYNC_CHANNEL`, polling only catches the ones missed meanwhile.
+pub const SYNC_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+pub const SYNC_LISTEN_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub const SYNC_EVENTS_CAPACITY: usize = 64;
+
+/// Comments sent on idle `/api/events` streams, so proxies don't close them.
+pub const SSE_HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Bearer token of the `/api/admin` routes, which are disabled without it.
+pub fn admin_token() -> Option<String> {
+    std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty())
+}
+
+pub const WEBHOOK_DELIVERIES_LIMIT: i64 = 50;
+
+pub const CHANGES_PAGE_SIZE: i64 = 500;
@@ -0,0 +1,363 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use service::resolve::ChampionResolver;
+
+use crate::{
+    config,
+    errors::CustomError,
+    events::{self, SyncEvents},
+};
+
+/// (version, locale, file), version and locale are empty for files shared by all of them.
+type CacheKey = (String, String, String);
+
+/// Built from the `champion.json` of a locale.
+type CachedResolver = (Instant, Arc<ChampionResolver>);
+
+/// Loads Data Dragon files from a per-locale in-process cache, then the DB, then ddragon itself.
+#[derive(Clone)]
+pub struct DataDragon {
+    pool: db::DbPool,
+    agent: ureq::Agent,
+    cache: Arc<RwLock<HashMap<CacheKey, (Instant, Value)>>>,
+    /// URLs whose last fetch failed, so an unreachable ddragon isn't waited on by every request
+    failures: Arc<RwLock<HashMap<String, Instant>>>,
+    refreshing_versions: Arc<AtomicBool>,
+    resolvers: Arc<RwLock<HashMap<String, CachedResolver>>>,
+}
+
+impl DataDragon {
+    pub fn new(pool: db::DbPool, agent: ureq::Agent) -> Self {
+        Self {
+            pool,
+            agent,
+            cache: Default::default(),
+            failures: Default::default(),
+            refreshing_versions: Default::default(),
+            resolvers: Default::default(),
+        }
+    }
+
+    /// Loads a file of the given patch version, or of the latest one when `version` is `None`.
+    pub async fn load<T: DeserializeOwned>(
+        &self,
+        version: Option<&str>,
+        locale: &str,
+        file: &str,
+    ) -> anyhow::Result<T> {
+        let key = (
+            version.unwrap_or_default().to_string(),
+            locale.to_string(),
+            file.to_string(),
+        );
+        if let Some(value) = self.cached(&key) {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let stored = match version {
+            Some(version) => {
+                db::find_ddragon_file(
+                    self.pool.clone(),
+                    version.to_string(),
+                    file.to_string(),
+                    locale.to_string(),
+                )
+                .await?
+            }
+            None => {
+                db::find_latest_ddragon_file(
+                    self.pool.clone(),
+                    file.to_string(),
+                    locale.to_string(),
+                )
+                .await?
+            }
+        };
+        let value = match stored {
+            Some(f) => f.content,
+            None => {
+                let version = match version {
+                    Some(v) => v.to_string(),
+                    None => self
+                        .versions()
+                        .await?
+                        .first()
+                        .cloned()
+                        .ok_or(anyhow::anyhow!("No lol version found"))?,
+                };
+                let url = format!(
+                    "{}/cdn/{version}/data/{locale}/{file}",
+                    config::data_dragon_url()
+                );
+                self.fetch(url).await?
+            }
+        };
+        self.insert(key, value.clone());
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub async fn languages(&self) -> anyhow::Result<Vec<String>> {
+        let key = (String::new(), String::new(), String::from("languages.json"));
+        if let Some(value) = self.cached(&key) {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let url = format!("{}/cdn/languages.json", config::data_dragon_url());
+        let value = self.fetch(url).await?;
+        self.insert(key, value.clone());
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// All patch versions, newest first.
+    pub async fn versions(&self) -> anyhow::Result<Vec<String>> {
+        let key = (String::new(), String::new(), String::from("versions.json"));
+        if let Some(value) = self.cached(&key) {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let url = format!("{}/api/versions.json", config::data_dragon_url());
+        let fetched = self
+            .fetch(url)
+            .await
+            .and_then(|v| Ok(serde_json::from_value::<Vec<String>>(v)?));
+        let versions = match fetched {
+            Ok(versions) => versions,
+            Err(e) => {
+                let stored = db::list_ddragon_versions(self.pool.clone()).await?;
+                tracing::warn!("failed to list lol versions, using synced ones: {e}");
+                if stored.is_empty() {
+                    anyhow::bail!("Failed to list lol versions");
+                }
+                stored
+            }
+        };
+        self.insert(key, serde_json::to_value(&versions)?);
+
+        Ok(versions)
+    }
+
+    /// Versions for requests that shouldn't wait on ddragon: the cached ones, or else the synced
+    /// ones while ddragon's are fetched in the background. Empty when neither is available.
+    pub async fn versions_or_synced(&self) -> Vec<String> {
+        let key = (String::new(), String::new(), String::from("versions.json"));
+        if let Some(value) = self.cached(&key) {
+            return serde_json::from_value(value).unwrap_or_default();
+        }
+
+        if !self.refreshing_versions.swap(true, Ordering::AcqRel) {
+            let ddragon = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = ddragon.versions().await {
+                    tracing::warn!("failed to refresh lol versions: {e}");
+                }
+                ddragon.refreshing_versions.store(false, Ordering::Release);
+            });
+        }
+        match db::list_ddragon_versions(self.pool.clone()).await {
+            Ok(versions) => versions,
+            Err(e) => {
+                tracing::warn!("failed to list synced lol versions: {e}");
+                vec![]
+            }
+        }
+    }
+
+    /// Locales stored by `sync-ddragon`.
+    pub async fn synced_locales(&self) -> Vec<String> {
+        let key = (String::new(), String::new(), String::from("locales"));
+        if let Some(value) = self.cached(&key) {
+            return serde_json::from_value(value).unwrap_or_default();
+        }
+
+        match db::list_ddragon_locales(self.pool.clone()).await {
+            Ok(locales) => {
+                self.insert(key, serde_json::to_value(&locales).unwrap_or_default());
+                locales
+            }
+            Err(e) => {
+                tracing::warn!("failed to list synced locales: {e}");
+                vec![]
+            }
+        }
+    }
+
+    /// Defaults to `en_US`, otherwise the locale must be synced or listed in ddragon's
+    /// `languages.json`.
+    pub async fn resolve_locale(&self, locale: Option<String>) -> Result<String, CustomError> {
+        let Some(locale) = locale else {
+            return Ok(service::DEFAULT_LOCALE.to_string());
+        };
+        if self.synced_locales().await.contains(&locale) {
+            return Ok(locale);
+        }
+
+        match self.languages().await {
+            Ok(languages) if languages.contains(&locale) => Ok(locale),
+            Ok(_) => Err(CustomError::BadRequest),
+            // ddragon is unreachable, only accept what looks like a locale
+            Err(e) if locale.chars().all(|c| c.is_ascii_alphabetic() || c == '_') => {
+                tracing::warn!("failed to list languages, skipped checking {locale}: {e}");
+                Ok(locale)
+            }
+            Err(_) => Err(CustomError::BadRequest),
+        }
+    }
+
+    /// Accepts a full version like `13.22.1`, or a `13.22` as found in `Build::official_version`,
+    /// which resolves to the newest matching patch.
+    pub async fn resolve_version(
+        &self,
+        version: Option<String>,
+    ) -> Result<Option<String>, CustomError> {
+        let Some(version) = version else {
+            return Ok(None);
+        };
+
+        let versions = self.versions().await?;
+        match_version(versions, &version)
+            .map(Some)
+            .ok_or(CustomError::BadRequest)
+    }
+
+    pub async fn names(&self, locale: &str) -> anyhow::Result<service::compare::NameMap> {
+        let champions = self
+            .load::<service::ChampionMapResp>(None, locale, "champion.json")
+            .await?;
+        let rune_styles = self
+            .load::<Vec<service::RuneStyle>>(None, locale, "runesReforged.json")
+            .await?;
+        let items = self
+            .load::<service::ItemMapResp>(None, locale, "item.json")
+            .await?;
+
+        Ok(service::compare::NameMap::new(
+            &champions,
+            &rune_styles,
+            &items,
+        ))
+    }
+
+    pub async fn champions(&self, locale: &str) -> anyhow::Result<Arc<ChampionResolver>> {
+        let cached = self.resolvers.read().unwrap().get(locale).cloned();
+        if let Some((at, resolver)) = cached {
+            if at.elapsed() < config::DATA_DRAGON_CACHE_TTL {
+                return Ok(resolver);
+            }
+        }
+
+        let champions = self
+            .load::<service::ChampionMapResp>(None, locale, "champion.json")
+            .await?;
+        let resolver = Arc::new(ChampionResolver::new(&champions));
+        self.resolvers
+            .write()
+            .unwrap()
+            .insert(locale.to_string(), (Instant::now(), resolver.clone()));
+        Ok(resolver)
+    }
+
+    /// Drops cached files once a new patch is synced, instead of waiting out their TTL.
+    pub fn watch(&self, events: &SyncEvents) {
+        let ddragon = self.clone();
+        events::subscribe_with(events, move |event| {
+            if event.is_none_or(|e| e.action == "sync_ddragon") {
+                ddragon.cache.write().unwrap().clear();
+                ddragon.resolvers.write().unwrap().clear();
+            }
+            std::future::ready(())
+        });
+    }
+
+    /// GETs a JSON file from ddragon without blocking the runtime. A failed URL isn't fetched
+    /// again until `DATA_DRAGON_FAILURE_TTL` has passed, it fails right away meanwhile.
+    async fn fetch(&self, url: String) -> anyhow::Result<Value> {
+        let failed_at = self.failures.read().unwrap().get(&url).copied();
+        if failed_at.is_some_and(|at| at.elapsed() < config::DATA_DRAGON_FAILURE_TTL) {
+            anyhow::bail!("fetching {url} failed recently, not retried yet");
+        }
+
+        let agent = self.agent.clone();
+        let fetch_url = url.clone();
+        let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Value> {
+            Ok(agent.get(&fetch_url).call()?.into_json::<Value>()?)
+        })
+        .await?;
+
+        let mut failures = self.failures.write().unwrap();
+        match result {
+            Ok(_) => failures.remove(&url),
+            Err(_) => failures.insert(url, Instant::now()),
+        };
+        result
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<Value> {
+        let cache = self.cache.read().unwrap();
+        cache
+            .get(key)
+            .filter(|(at, _)| at.elapsed() < config::DATA_DRAGON_CACHE_TTL)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn insert(&self, key: CacheKey, value: Value) {
+        self.cache
+            .write()
+            .unwrap()
+            .insert(key, (Instant::now(), value));
+    }
+}
+
+/// `version` itself if it's one of `versions`, else the first, i.e. the newest, patch of it.
+fn match_version(versions: Vec<String>, version: &str) -> Option<String> {
+    if versions.iter().any(|v| v == version) {
+        return Some(version.to_string());
+    }
+    let prefix = format!("{version}.");
+    versions.into_iter().find(|v| v.starts_with(&prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions() -> Vec<String> {
+        ["13.22.1", "13.22.0", "13.21.1", "13.2.1"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn matches_versions_exactly() {
+        assert_eq!(
+            match_version(versions(), "13.22.0").as_deref(),
+            Some("13.22.0")
+        );
+    }
+
+    #[test]
+    fn matches_the_newest_patch_of_a_prefix() {
+        assert_eq!(
+            match_version(versions(), "13.22").as_deref(),
+            Some("13.22.1")
+        );
+        assert_eq!(match_version(versions(), "13.2").as_deref(), Some("13.2.1"));
+    }
+
+    #[test]
+    fn unknown_versions_have_no_match() {
+        assert_eq!(match_version(versions(), "13.23"), None);
+        assert_eq!(match_version(versions(), "13.22.1.0"), None);
+        assert_eq!(match_version(versions(), "14"), None);
+    }
+}
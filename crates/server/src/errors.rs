@@ -6,6 +6,8 @@ use axum::{
 use serde_json::json;
 pub enum CustomError {
     BadRequest,
+    Unauthorized,
+    NotFound,
     InternalServerError,
 }
 
@@ -16,6 +18,8 @@ impl IntoResponse for CustomError {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
             }
             Self::BadRequest => (StatusCode::BAD_REQUEST, "Bad Request"),
+            Self::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            Self::NotFound => (StatusCode::NOT_FOUND, "Not Found"),
         };
         (status, Json(json!({ "error": error_message }))).into_response()
     }
@@ -0,0 +1,70 @@
+use std::future::Future;
+
+use db::models::SyncEvent;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::config;
+
+/// Fans the syncs announced on `db::SYNC_CHANNEL` out to everything in the server that cares.
+#[derive(Clone)]
+pub struct SyncEvents {
+    sender: broadcast::Sender<SyncEvent>,
+}
+
+impl SyncEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(config::SYNC_EVENTS_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SyncEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Listens in the background, reconnecting whenever the connection is lost.
+    pub fn listen(&self) {
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            loop {
+                let result = db::listen_syncs(|event| match event {
+                    Ok(event) => {
+                        tracing::info!("received {} of {:?}", event.action, event.sources);
+                        // no subscribers is fine
+                        let _ = sender.send(event);
+                    }
+                    Err(e) => tracing::warn!("{e:#}"),
+                })
+                .await;
+                match result {
+                    Ok(_) => tracing::warn!("sync listener disconnected, reconnecting"),
+                    Err(e) => tracing::warn!("sync listener failed, reconnecting: {e}"),
+                }
+                tokio::time::sleep(config::SYNC_LISTEN_RETRY_DELAY).await;
+            }
+        });
+    }
+}
+
+impl Default for SyncEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calls `on_event` for every sync, or with `None` when some were missed because it fell behind.
+pub fn subscribe_with<F, Fut>(events: &SyncEvents, mut on_event: F)
+where
+    F: FnMut(Option<SyncEvent>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => on_event(Some(event)).await,
+                Err(RecvError::Lagged(_)) => on_event(None).await,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
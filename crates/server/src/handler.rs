@@ -1,84 +1,1184 @@
-use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use axum::{
+    extract::{Path, Query, RawQuery},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use futures::stream;
+use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
+use service::compare::LocalizedNames;
+use service::filter::{BuildFilter, Position};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use subtle::ConstantTimeEq;
+use tokio::sync::broadcast::error::RecvError;
 
-use crate::{config, errors::CustomError};
+use crate::{
+    assets::AssetCache,
+    build_cache::{BuildCache, Lookup},
+    config,
+    ddragon::DataDragon,
+    errors::CustomError,
+    http_cache::Validators,
+    snapshot::{self, SnapshotFormat, Snapshots},
+    updates::Updates,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Staleness {
+    /// newest official version among the source's builds
+    pub official_version: String,
+    pub patch_lag: Option<usize>,
+    pub stale: bool,
+    /// champions whose builds are on an older official version
+    pub outdated_champions: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceResponse {
+    #[serde(flatten)]
+    pub source: db::models::Source,
+    pub staleness: Option<Staleness>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    Aram,
+    Urf,
+}
+
+impl GameMode {
+    pub fn matches(&self, source: &db::models::Source) -> bool {
+        match self {
+            Self::Aram => source.is_aram,
+            Self::Urf => source.is_urf,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourcesQuery {
+    pub mode: Option<GameMode>,
+}
 
 pub async fn list_sources(
     Extension(pool): Extension<db::DbPool>,
-    // Extension(agent): Extension<ureq::Agent>,
-) -> Result<impl IntoResponse, CustomError> {
-    let sources = db::list_sources(pool).await?;
-    Ok(Json(sources))
+    Extension(ddragon): Extension<DataDragon>,
+    Query(query): Query<SourcesQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let last_sync = find_last_sync(pool.clone(), &["sync_sources", "sync_builds"]).await?;
+    let versions = ddragon.versions_or_synced().await;
+    // each mode lists other sources, so it's part of the etag
+    let key = (
+        last_sync.as_ref().map(|l| l.id),
+        versions.first(),
+        query.mode,
+    );
+    let validators = Validators::new(key).with_last_modified(last_sync.map(|l| l.created_at));
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let sources = db::list_active_sources(pool.clone()).await?;
+    let official_versions = db::list_source_official_versions(pool).await?;
+
+    let resp = sources
+        .into_iter()
+        .filter(|source| query.mode.is_none_or(|mode| mode.matches(source)))
+        .map(|source| {
+            let staleness = staleness_of(&source.source, &official_versions, &versions);
+            SourceResponse { source, staleness }
+        })
+        .collect::<Vec<SourceResponse>>();
+    Ok(validators.respond(Json(resp)))
+}
+
+async fn find_last_sync(
+    pool: db::DbPool,
+    actions: &[&str],
+) -> anyhow::Result<Option<db::models::Log>> {
+    let actions = actions.iter().map(|a| a.to_string()).collect();
+    db::find_latest_log(pool, actions).await
+}
+
+fn staleness_of(
+    source: &str,
+    official_versions: &[db::models::SourceOfficialVersion],
+    versions: &[String],
+) -> Option<Staleness> {
+    let mut rows = official_versions
+        .iter()
+        .filter(|r| r.source == source)
+        .filter_map(|r| {
+            let official_version = r.official_version.as_deref()?;
+            let lag = service::patch::patch_lag(versions, official_version);
+            Some((official_version, lag, r.champions))
+        })
+        .collect::<Vec<(&str, Option<usize>, i64)>>();
+    // unknown patches sort last
+    rows.sort_by_key(|(_, lag, _)| lag.unwrap_or(usize::MAX));
+
+    let (official_version, patch_lag, _) = *rows.first()?;
+    let outdated_champions = rows
+        .iter()
+        .filter(|(v, _, _)| *v != official_version)
+        .map(|(_, _, n)| n)
+        .sum();
+    Some(Staleness {
+        official_version: official_version.to_string(),
+        patch_lag,
+        stale: is_stale(patch_lag),
+        outdated_champions,
+    })
+}
+
+fn is_stale(patch_lag: Option<usize>) -> bool {
+    patch_lag.is_some_and(|lag| lag > config::stale_patch_threshold())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocaleQuery {
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildResponse {
+    #[serde(flatten)]
+    pub build: db::models::Build,
+    /// patches between the builds' official version and the latest one
+    pub patch_lag: Option<usize>,
+    pub stale: bool,
+    /// only present when a `locale` is requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub names: Option<LocalizedNames>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_builds_by_alias(
     Extension(pool): Extension<db::DbPool>,
-    // Extension(agent): Extension<ureq::Agent>,
+    Extension(ddragon): Extension<DataDragon>,
+    Extension(cache): Extension<BuildCache>,
     Path((source, champion)): Path<(String, String)>,
-) -> Result<impl IntoResponse, CustomError> {
-    let b = db::find_builds_by_champion_alias_and_source(pool, champion, source).await?;
-    Ok(Json(b))
+    Query(filter): Query<BuildFilter>,
+    Query(query): Query<LocaleQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let b = find_build(pool, &ddragon, &cache, source, champion).await?;
+    let validators = build_validators(&ddragon, &cache, &b, raw_query).await;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let resp = make_build_response(&ddragon, b, &filter, query.locale).await?;
+    Ok(validators.respond(Json(resp)))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_builds_by_champion_id(
     Extension(pool): Extension<db::DbPool>,
-    // Extension(agent): Extension<ureq::Agent>,
+    Extension(ddragon): Extension<DataDragon>,
+    Extension(cache): Extension<BuildCache>,
     Path((source, champion_id)): Path<(String, String)>,
+    Query(filter): Query<BuildFilter>,
+    Query(query): Query<LocaleQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let b = find_build(pool, &ddragon, &cache, source, champion_id).await?;
+    let validators = build_validators(&ddragon, &cache, &b, raw_query).await;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let resp = make_build_response(&ddragon, b, &filter, query.locale).await?;
+    Ok(validators.respond(Json(resp)))
+}
+
+/// A response is decided by the build, the query that picks its representation, and the latest
+/// patch its lag and names follow, so it can be validated before it's built.
+async fn build_validators(
+    ddragon: &DataDragon,
+    cache: &BuildCache,
+    build: &db::models::Build,
+    raw_query: Option<String>,
+) -> Validators {
+    let versions = ddragon.versions_or_synced().await;
+    let last_sync = cache.last_sync();
+    Validators::new((
+        &build.source,
+        &build.champion_alias,
+        &build.version,
+        &build.content,
+        raw_query,
+        versions.first(),
+    ))
+    .with_last_modified(last_sync.map(|l| l.created_at))
+}
+
+/// Looks a champion up by the input itself and its exact match, ignoring case and punctuation.
+/// Only when that finds nothing, and the input isn't a numeric id, the closest champion within a
+/// typo or two is tried, so a typo never wins over a champion that exists.
+async fn find_champion<T, F, Fut>(
+    ddragon: &DataDragon,
+    champion: &str,
+    lookup: F,
+) -> anyhow::Result<Option<T>>
+where
+    F: Fn(Vec<String>, Vec<String>) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
+{
+    let resolver = match ddragon.champions(service::DEFAULT_LOCALE).await {
+        Ok(resolver) => Some(resolver),
+        Err(e) => {
+            tracing::warn!("failed to load champions, skipped resolving {champion}: {e}");
+            None
+        }
+    };
+
+    let exact = resolver.as_ref().and_then(|r| r.resolve_exact(champion));
+    let mut aliases = vec![champion.to_string()];
+    let mut ids = vec![champion.to_string()];
+    if let Some(c) = exact {
+        aliases.push(c.id.clone());
+        ids.push(c.key.clone());
+    }
+    if let Some(found) = lookup(aliases, ids).await? {
+        return Ok(Some(found));
+    }
+
+    if exact.is_some() || champion.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+    match resolver.as_ref().and_then(|r| r.resolve(champion)) {
+        Some(c) => lookup(vec![c.id.clone()], vec![c.key.clone()]).await,
+        None => Ok(None),
+    }
+}
+
+async fn find_build(
+    pool: db::DbPool,
+    ddragon: &DataDragon,
+    cache: &BuildCache,
+    source: String,
+    champion: String,
+) -> Result<db::models::Build, CustomError> {
+    if let Some(b) = cache.get(Lookup::Resolved, &source, &champion) {
+        return Ok(b);
+    }
+
+    let generation = cache.generation();
+    let b = find_champion(ddragon, &champion, |aliases, ids| {
+        db::find_build_by_source_and_champion(pool.clone(), source.clone(), aliases, ids)
+    })
+    .await?
+    .ok_or(CustomError::NotFound)?;
+    cache.insert(generation, Lookup::Resolved, &source, &champion, b.clone());
+    Ok(b)
+}
+
+/// The builds of every source for a champion, `NotFound` if there are none.
+async fn find_champion_builds(
+    pool: db::DbPool,
+    ddragon: &DataDragon,
+    champion: &str,
+) -> Result<Vec<db::models::Build>, CustomError> {
+    let rows = find_champion(ddragon, champion, |aliases, ids| {
+        let pool = pool.clone();
+        async move {
+            let rows = db::find_builds_by_champion(pool, aliases, ids).await?;
+            Ok(Some(rows).filter(|r| !r.is_empty()))
+        }
+    })
+    .await?;
+    rows.ok_or(CustomError::NotFound)
+}
+
+pub async fn get_cache_stats(
+    Extension(cache): Extension<BuildCache>,
 ) -> Result<impl IntoResponse, CustomError> {
-    let b = db::find_builds_by_champion_id_and_source(pool, champion_id, source).await?;
-    Ok(Json(b))
+    Ok(Json(cache.stats()))
 }
 
-pub async fn get_latest_lol_version(agent: &ureq::Agent) -> Result<String, StatusCode> {
-    let version_list_url = format!("{}/api/versions.json", config::DATA_DRAGON_URL);
-    let body = agent
-        .get(&version_list_url)
-        .call()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .into_json::<Vec<String>>()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let latest_version = body.first().unwrap().to_string();
+/// Server-Sent Events of source updates and new patches, for clients that would otherwise poll.
+/// The data of each is the body a webhook subscribed to that event receives.
+pub async fn stream_events(Extension(updates): Extension<Updates>) -> impl IntoResponse {
+    let events = stream::unfold(updates.subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(update) => {
+                    let event = Event::default().event(update.name()).json_data(&update);
+                    return Some((event, receiver));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("event stream fell behind, skipped {skipped} updates");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
 
-    Ok(latest_version)
+    (
+        // nginx would otherwise buffer the stream
+        [("x-accel-buffering", "no")],
+        Sse::new(events).keep_alive(
+            KeepAlive::new()
+                .interval(config::SSE_HEARTBEAT_INTERVAL)
+                .text("heartbeat"),
+        ),
+    )
 }
 
-pub async fn list_champion_map(
-    Extension(agent): Extension<ureq::Agent>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let latest_version = get_latest_lol_version(&agent).await?;
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChampionSearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+    pub locale: Option<String>,
+}
 
-    let champion_map_url = format!(
-        "{}/cdn/{latest_version}/data/en_US/champion.json",
-        config::DATA_DRAGON_URL
-    );
-    let body = agent
-        .get(&champion_map_url)
-        .call()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .into_json::<Value>()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let data = body["data"].to_string();
-    let champion_map =
-        serde_json::from_str::<Value>(&data).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+pub async fn search_champions(
+    Extension(ddragon): Extension<DataDragon>,
+    Query(query): Query<ChampionSearchQuery>,
+) -> Result<impl IntoResponse, CustomError> {
+    let locale = ddragon.resolve_locale(query.locale).await?;
+    let resolver = ddragon.champions(&locale).await?;
+    let limit = query
+        .limit
+        .unwrap_or(config::CHAMPION_SEARCH_LIMIT)
+        .min(config::CHAMPION_SEARCH_LIMIT);
+
+    let champions = resolver
+        .search(&query.q, limit)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<service::Champion>>();
+    Ok(Json(champions))
+}
+
+async fn make_build_response(
+    ddragon: &DataDragon,
+    build: db::models::Build,
+    filter: &BuildFilter,
+    locale: Option<String>,
+) -> Result<BuildResponse, CustomError> {
+    let build = filter_builds(build, filter)?;
+
+    // without any versions nothing can be compared, so every lag is unknown rather than an error
+    let versions = ddragon.versions_or_synced().await;
+    let patch_lag = build
+        .content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|b| b["officialVersion"].as_str())
+        .filter_map(|v| service::patch::patch_lag(&versions, v))
+        .max();
+
+    let names = match locale {
+        Some(locale) => {
+            let locale = ddragon.resolve_locale(Some(locale)).await?;
+            let names = ddragon.names(&locale).await?;
+            let builds = serde_json::from_value::<Vec<service::Build>>(build.content.clone())
+                .map_err(anyhow::Error::from)?;
+            Some(names.localize(&builds))
+        }
+        None => None,
+    };
+
+    Ok(BuildResponse {
+        build,
+        patch_lag,
+        stale: is_stale(patch_lag),
+        names,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchEntry {
+    pub source: String,
+    /// champion alias or champion id
+    pub champion: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchResult {
+    Found { build: db::models::Build },
+    NotFound,
+}
+
+/// Keyed by source, then by the champion as it was requested.
+pub type BatchResponse = HashMap<String, HashMap<String, BatchResult>>;
+
+pub async fn get_builds_batch(
+    Extension(pool): Extension<db::DbPool>,
+    Extension(cache): Extension<BuildCache>,
+    Json(entries): Json<Vec<BatchEntry>>,
+) -> Result<impl IntoResponse, CustomError> {
+    if entries.is_empty() || entries.len() > config::MAX_BATCH_SIZE {
+        return Err(CustomError::BadRequest);
+    }
+
+    let cached = entries
+        .iter()
+        .map(|e| cache.get(Lookup::Exact, &e.source, &e.champion))
+        .collect::<Vec<Option<db::models::Build>>>();
+    let generation = cache.generation();
+    let pairs = entries
+        .iter()
+        .zip(&cached)
+        .filter(|(_, b)| b.is_none())
+        .map(|(e, _)| (e.source.clone(), e.champion.clone()))
+        .collect();
+    let rows = db::find_builds_by_source_and_champion_pairs(pool, pairs).await?;
+
+    Ok(Json(answer_batch(
+        &cache, generation, entries, cached, &rows,
+    )))
+}
+
+/// Answers each entry with its cached build, or else with the row of its source whose alias or
+/// id is the champion, which is then cached.
+fn answer_batch(
+    cache: &BuildCache,
+    generation: u64,
+    entries: Vec<BatchEntry>,
+    cached: Vec<Option<db::models::Build>>,
+    rows: &[db::models::Build],
+) -> BatchResponse {
+    let mut resp = BatchResponse::new();
+    for (entry, cached) in entries.into_iter().zip(cached) {
+        let found = cached.or_else(|| {
+            let b = rows.iter().find(|b| {
+                b.source == entry.source
+                    && (b.champion_alias == entry.champion || b.champion_id == entry.champion)
+            })?;
+            cache.insert(
+                generation,
+                Lookup::Exact,
+                &entry.source,
+                &entry.champion,
+                b.clone(),
+            );
+            Some(b.clone())
+        });
+        let result = match found {
+            Some(build) => BatchResult::Found { build },
+            None => BatchResult::NotFound,
+        };
+        resp.entry(entry.source)
+            .or_default()
+            .insert(entry.champion, result);
+    }
+    resp
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChangesQuery {
+    /// the `cursor` of the previous response, everything when omitted
+    pub since: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub source: String,
+    pub champion_alias: String,
+    pub champion_id: String,
+    /// `added`, `updated` or `deleted`
+    pub kind: String,
+    /// the current build, `None` once deleted
+    pub build: Option<db::models::Build>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangesResponse {
+    pub cursor: i64,
+    /// more changes are past `cursor`, request again with it
+    pub has_more: bool,
+    pub changes: Vec<Change>,
+}
+
+/// Builds changed after a cursor, so clients with a local copy can catch up incrementally.
+pub async fn list_changes(
+    Extension(pool): Extension<db::DbPool>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<impl IntoResponse, CustomError> {
+    let since = query.since.unwrap_or(0);
+    if since < 0 {
+        return Err(CustomError::BadRequest);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(config::CHANGES_PAGE_SIZE)
+        .clamp(1, config::CHANGES_PAGE_SIZE);
+
+    let rows = db::list_build_changes(pool.clone(), since, limit).await?;
+    let has_more = rows.len() as i64 == limit;
+    let cursor = rows.last().map_or(since, |c| c.seq);
+
+    // only the latest change of each build matters
+    let mut seen = std::collections::HashSet::new();
+    let mut rows = rows
+        .into_iter()
+        .rev()
+        .filter(|c| seen.insert((c.source.clone(), c.champion_alias.clone())))
+        .collect::<Vec<db::models::BuildChange>>();
+    rows.reverse();
+
+    let pairs = rows
+        .iter()
+        .filter(|c| c.kind != "deleted")
+        .map(|c| (c.source.clone(), c.champion_alias.clone()))
+        .collect();
+    let builds = db::find_builds_by_source_and_champion_pairs(pool, pairs).await?;
+
+    let changes = rows
+        .into_iter()
+        .map(|c| {
+            let build = builds
+                .iter()
+                .find(|b| b.source == c.source && b.champion_alias == c.champion_alias)
+                .filter(|_| c.kind != "deleted")
+                .cloned();
+            Change {
+                source: c.source,
+                champion_alias: c.champion_alias,
+                champion_id: c.champion_id,
+                kind: c.kind,
+                build,
+            }
+        })
+        .collect();
+
+    Ok(Json(ChangesResponse {
+        cursor,
+        has_more,
+        changes,
+    }))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConsensusQuery {
+    pub position: Option<Position>,
+}
+
+pub async fn get_consensus(
+    Extension(pool): Extension<db::DbPool>,
+    Extension(ddragon): Extension<DataDragon>,
+    Path(champion): Path<String>,
+    Query(query): Query<ConsensusQuery>,
+) -> Result<impl IntoResponse, CustomError> {
+    let rows = find_champion_builds(pool, &ddragon, &champion).await?;
+    let builds_by_source = parse_builds(rows);
+    let consensus = service::consensus::build_consensus(champion, builds_by_source, query.position);
+    Ok(Json(consensus))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompareQuery {
+    /// comma separated source names, all sources when omitted
+    pub sources: Option<String>,
+    pub position: Option<Position>,
+    pub locale: Option<String>,
+}
+
+pub async fn compare_builds(
+    Extension(pool): Extension<db::DbPool>,
+    Extension(ddragon): Extension<DataDragon>,
+    Path(champion): Path<String>,
+    Query(query): Query<CompareQuery>,
+) -> Result<impl IntoResponse, CustomError> {
+    let wanted = query.sources.map(|s| {
+        s.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<String>>()
+    });
+    let rows = find_champion_builds(pool, &ddragon, &champion)
+        .await?
+        .into_iter()
+        .filter(|b| wanted.as_ref().is_none_or(|w| w.contains(&b.source)))
+        .collect::<Vec<db::models::Build>>();
+    if rows.is_empty() {
+        return Err(CustomError::NotFound);
+    }
+
+    let locale = ddragon.resolve_locale(query.locale).await?;
+    let names = ddragon.names(&locale).await?;
+
+    let comparison =
+        service::compare::compare_builds(champion, parse_builds(rows), query.position, &names);
+    Ok(Json(comparison))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiffQuery {
+    pub from: String,
+    pub to: String,
+}
+
+pub async fn diff_builds(
+    Path((source, champion)): Path<(String, String)>,
+    Query(query): Query<DiffQuery>,
+) -> Result<impl IntoResponse, CustomError> {
+    // all of them end up in the CDN path
+    let valid = service::is_identifier(&source)
+        && service::is_identifier(&champion)
+        && service::is_package_version(&query.from)
+        && service::is_package_version(&query.to);
+    if !valid {
+        return Err(CustomError::BadRequest);
+    }
 
-    Ok(Json(champion_map))
+    let (from, to) = tokio::try_join!(
+        service::get_champion_build(champion.clone(), source.clone(), query.from),
+        service::get_champion_build(champion, source, query.to),
+    )
+    .map_err(anyhow::Error::from)?;
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err(CustomError::NotFound);
+    };
+
+    Ok(Json(service::diff::diff_builds(&from, &to)))
+}
+
+/// Rune pages of a champion, ready to `POST` to the League Client's `/lol-perks/v1/pages`.
+pub async fn get_rune_pages(
+    Extension(pool): Extension<db::DbPool>,
+    Extension(ddragon): Extension<DataDragon>,
+    Extension(cache): Extension<BuildCache>,
+    Path((source, champion)): Path<(String, String)>,
+    Query(filter): Query<BuildFilter>,
+) -> Result<impl IntoResponse, CustomError> {
+    let b = find_build(pool, &ddragon, &cache, source.clone(), champion).await?;
+    let builds =
+        serde_json::from_value::<Vec<service::Build>>(b.content).map_err(anyhow::Error::from)?;
+    let builds = filter.apply(builds);
+
+    let rune_styles = ddragon
+        .load::<Vec<service::RuneStyle>>(None, service::DEFAULT_LOCALE, "runesReforged.json")
+        .await?;
+
+    let pages = service::rune_page::to_rune_pages(&source, &builds, &rune_styles);
+    Ok(Json(pages))
+}
+
+/// Zips every item build of a source into League client item-set files.
+pub async fn export_item_sets(
+    Extension(pool): Extension<db::DbPool>,
+    Path(source): Path<String>,
+) -> Result<impl IntoResponse, CustomError> {
+    let mut conn = db::get_conn(pool).await?;
+    let rows = db::list_builds_by_source(&mut conn, source.clone())
+        .await
+        .map_err(anyhow::Error::from)?;
+    if rows.is_empty() {
+        return Err(CustomError::NotFound);
+    }
+
+    let files = parse_builds(rows)
+        .iter()
+        .flat_map(|(_, builds)| service::itemset::item_set_files(&source, builds))
+        .map(|f| Ok((f.path, serde_json::to_vec_pretty(&f.item_set)?)))
+        .collect::<anyhow::Result<Vec<(String, Vec<u8>)>>>()?;
+    let body = snapshot::zip(&files)?;
+
+    let headers = [
+        (header::CONTENT_TYPE, String::from("application/zip")),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{source}-item-sets.zip\""),
+        ),
+    ];
+    Ok((headers, body))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnapshotQuery {
+    #[serde(default)]
+    pub format: SnapshotFormat,
+}
+
+/// Every champion's builds of a source (or `all` of them) in one archive, with a manifest.
+pub async fn get_snapshot(
+    Extension(pool): Extension<db::DbPool>,
+    Extension(snapshots): Extension<Snapshots>,
+    Path(source): Path<String>,
+    Query(query): Query<SnapshotQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    // read before the builds, changes made meanwhile are simply served again past the cursor.
+    // a source's snapshot stays valid while only other sources change
+    let of_source = Some(source.clone()).filter(|s| s != snapshot::ALL_SOURCES);
+    let cursor = db::latest_build_change_seq(pool, of_source).await?;
+    let validators = Validators::new((&source, query.format, cursor));
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let snapshot = snapshots
+        .get(&source, query.format, cursor)
+        .await?
+        .ok_or(CustomError::NotFound)?;
+    let resp_headers = [
+        (
+            header::CONTENT_TYPE,
+            query.format.content_type().to_string(),
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"quicklook-{source}-{cursor}.{}\"",
+                query.format.extension()
+            ),
+        ),
+        (
+            header::HeaderName::from_static("x-checksum-sha256"),
+            snapshot.sha256.clone(),
+        ),
+    ];
+    Ok(validators
+        .with_last_modified(Some(snapshot.created_at))
+        .respond((resp_headers, snapshot.body.clone())))
+}
+
+/// Parses the content of each row, skipping (and logging) rows that don't match `service::Build`.
+fn parse_builds(rows: Vec<db::models::Build>) -> Vec<(String, Vec<service::Build>)> {
+    rows.into_iter()
+        .filter_map(
+            |row| match serde_json::from_value::<Vec<service::Build>>(row.content) {
+                Ok(builds) => Some((row.source, builds)),
+                Err(e) => {
+                    tracing::warn!("invalid build content from {}: {}", row.source, e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+fn filter_builds(
+    mut build: db::models::Build,
+    filter: &BuildFilter,
+) -> anyhow::Result<db::models::Build> {
+    if filter.is_empty() {
+        return Ok(build);
+    }
+
+    let builds = serde_json::from_value::<Vec<service::Build>>(build.content)?;
+    build.content = serde_json::to_value(filter.apply(builds))?;
+    Ok(build)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DataDragonQuery {
+    pub locale: Option<String>,
+    /// patch version, the latest one when omitted
+    pub version: Option<String>,
+}
+
+pub async fn list_versions(
+    Extension(ddragon): Extension<DataDragon>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let versions = ddragon.versions().await?;
+    let validators = Validators::new(&versions);
+    Ok(validators.respond_to(&headers, Json(versions)))
+}
+
+pub async fn list_champion_map(
+    Extension(ddragon): Extension<DataDragon>,
+    Query(query): Query<DataDragonQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    data_dragon_response(&ddragon, &headers, query, "champion.json", true).await
 }
 
 pub async fn list_runes_reforged(
-    Extension(agent): Extension<ureq::Agent>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let latest_version = get_latest_lol_version(&agent).await?;
+    Extension(ddragon): Extension<DataDragon>,
+    Query(query): Query<DataDragonQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    data_dragon_response(&ddragon, &headers, query, "runesReforged.json", false).await
+}
+
+pub async fn list_items(
+    Extension(ddragon): Extension<DataDragon>,
+    Query(query): Query<DataDragonQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    data_dragon_response(&ddragon, &headers, query, "item.json", true).await
+}
+
+pub async fn list_summoner_spells(
+    Extension(ddragon): Extension<DataDragon>,
+    Query(query): Query<DataDragonQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    data_dragon_response(&ddragon, &headers, query, "summoner.json", true).await
+}
+
+/// A Data Dragon file of a fixed version and locale never changes, so it's validated by those alone.
+async fn data_dragon_response(
+    ddragon: &DataDragon,
+    headers: &HeaderMap,
+    query: DataDragonQuery,
+    file: &str,
+    data_only: bool,
+) -> Result<Response, CustomError> {
+    let locale = ddragon.resolve_locale(query.locale).await?;
+    let version = version_or_latest(ddragon, query.version).await?;
+    let validators = Validators::new((&version, &locale, file));
+    if validators.is_fresh(headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let body = ddragon.load::<Value>(Some(&version), &locale, file).await?;
+    let body = if data_only {
+        body["data"].clone()
+    } else {
+        body
+    };
+    Ok(validators.respond(Json(body)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetQuery {
+    /// patch version, the latest one when omitted
+    pub version: Option<String>,
+}
+
+/// Champion square icon, `:file` is `<champion>.png` with anything the resolver accepts.
+pub async fn get_champion_icon(
+    Extension(ddragon): Extension<DataDragon>,
+    Extension(assets): Extension<AssetCache>,
+    Path(file): Path<String>,
+    Query(query): Query<AssetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let name = asset_name(&file)?;
+    let alias = match ddragon.champions(service::DEFAULT_LOCALE).await {
+        Ok(resolver) => resolver.resolve(name).map(|c| c.id.clone()),
+        Err(_) => None,
+    }
+    .unwrap_or(name.to_string());
+    let requested = query.version.clone();
+    let version = version_or_latest(&ddragon, query.version).await?;
+    let pinned = requested.as_ref() == Some(&version);
+
+    let url = format!(
+        "{}/cdn/{version}/img/champion/{alias}.png",
+        config::data_dragon_url()
+    );
+    serve_asset(
+        &assets, &headers, &version, pinned, "champion", &alias, &url,
+    )
+    .await
+}
+
+pub async fn get_item_icon(
+    Extension(ddragon): Extension<DataDragon>,
+    Extension(assets): Extension<AssetCache>,
+    Path(file): Path<String>,
+    Query(query): Query<AssetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let id = asset_name(&file)?;
+    let requested = query.version.clone();
+    let version = version_or_latest(&ddragon, query.version).await?;
+    let pinned = requested.as_ref() == Some(&version);
 
-    let runes_url = format!(
-        "{}/cdn/{latest_version}/data/en_US/runesReforged.json",
-        config::DATA_DRAGON_URL
+    let url = format!(
+        "{}/cdn/{version}/img/item/{id}.png",
+        config::data_dragon_url()
     );
-    let body = agent
-        .get(&runes_url)
-        .call()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .into_json::<Value>()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Json(body))
+    serve_asset(&assets, &headers, &version, pinned, "item", id, &url).await
+}
+
+/// Icon of a perk or a rune style, looked up in `runesReforged.json`.
+pub async fn get_perk_icon(
+    Extension(ddragon): Extension<DataDragon>,
+    Extension(assets): Extension<AssetCache>,
+    Path(file): Path<String>,
+    Query(query): Query<AssetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, CustomError> {
+    let id = asset_name(&file)?;
+    let perk_id = id.parse::<u64>().map_err(|_| CustomError::NotFound)?;
+    let requested = query.version.clone();
+    let version = version_or_latest(&ddragon, query.version).await?;
+    let pinned = requested.as_ref() == Some(&version);
+
+    let rune_styles = ddragon
+        .load::<Vec<service::RuneStyle>>(
+            Some(&version),
+            service::DEFAULT_LOCALE,
+            "runesReforged.json",
+        )
+        .await?;
+    let icon = rune_styles
+        .iter()
+        .flat_map(|style| {
+            let perks = style.slots.iter().flat_map(|s| s.runes.iter());
+            std::iter::once((style.id, &style.icon)).chain(perks.map(|p| (p.id, &p.icon)))
+        })
+        .find(|(id, _)| *id == perk_id)
+        .map(|(_, icon)| icon)
+        .ok_or(CustomError::NotFound)?;
+
+    // perk images aren't versioned on ddragon
+    let url = format!("{}/cdn/img/{icon}", config::data_dragon_url());
+    serve_asset(&assets, &headers, &version, pinned, "perk", id, &url).await
+}
+
+/// Strips `.png`, only plain alphanumeric names are accepted so they are safe to use as paths.
+fn asset_name(file: &str) -> Result<&str, CustomError> {
+    match file.strip_suffix(".png") {
+        Some(name) if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            Ok(name)
+        }
+        _ => Err(CustomError::NotFound),
+    }
+}
+
+async fn version_or_latest(
+    ddragon: &DataDragon,
+    version: Option<String>,
+) -> Result<String, CustomError> {
+    if let Some(version) = ddragon.resolve_version(version).await? {
+        return Ok(version);
+    }
+
+    let versions = ddragon.versions().await?;
+    versions
+        .first()
+        .cloned()
+        .ok_or(CustomError::InternalServerError)
+}
+
+async fn serve_asset(
+    assets: &AssetCache,
+    headers: &HeaderMap,
+    version: &str,
+    pinned: bool,
+    kind: &str,
+    name: &str,
+    url: &str,
+) -> Result<Response, CustomError> {
+    let mut validators = Validators::new((version, kind, name));
+    // the latest icon, or that of the newest `13.24.x`, changes with the next patch
+    if pinned {
+        validators =
+            validators.with_cache_control(format!("public, max-age={}", config::ASSET_MAX_AGE));
+    }
+    if validators.is_fresh(headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let key = PathBuf::from(version)
+        .join(kind)
+        .join(format!("{name}.png"));
+    let body = assets.get(&key, url).await?.ok_or(CustomError::NotFound)?;
+
+    let headers = [(header::CONTENT_TYPE, "image/png")];
+    Ok(validators.respond((headers, body)))
+}
+
+/// Admin routes need `Authorization: Bearer <ADMIN_TOKEN>`, and don't exist without the token.
+fn authorize(headers: &HeaderMap) -> Result<(), CustomError> {
+    let token = config::admin_token().ok_or(CustomError::NotFound)?;
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match given {
+        // compared in constant time, so the token can't be guessed from response times
+        Some(given) if bool::from(given.as_bytes().ct_eq(token.as_bytes())) => Ok(()),
+        _ => Err(CustomError::Unauthorized),
+    }
+}
+
+pub async fn list_webhooks(
+    Extension(pool): Extension<db::DbPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, CustomError> {
+    authorize(&headers)?;
+    let webhooks = db::list_webhooks(pool).await?;
+    Ok(Json(webhooks))
+}
+
+pub async fn create_webhook(
+    Extension(pool): Extension<db::DbPool>,
+    headers: HeaderMap,
+    Json(new_webhook): Json<db::models::NewWebhook>,
+) -> Result<impl IntoResponse, CustomError> {
+    authorize(&headers)?;
+    service::webhook::validate(&new_webhook.url, &new_webhook.events)
+        .map_err(|_| CustomError::BadRequest)?;
+    let webhook = db::insert_webhook(pool, new_webhook).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+pub async fn delete_webhook(
+    Extension(pool): Extension<db::DbPool>,
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    authorize(&headers)?;
+    if !db::delete_webhook(pool, id).await? {
+        return Err(CustomError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeliveriesQuery {
+    pub limit: Option<i64>,
+}
+
+pub async fn list_webhook_deliveries(
+    Extension(pool): Extension<db::DbPool>,
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<impl IntoResponse, CustomError> {
+    authorize(&headers)?;
+    let limit = query
+        .limit
+        .unwrap_or(config::WEBHOOK_DELIVERIES_LIMIT)
+        .clamp(1, config::WEBHOOK_DELIVERIES_LIMIT);
+    let deliveries = db::list_webhook_deliveries(pool, id, limit).await?;
+    Ok(Json(deliveries))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn row(source: &str, alias: &str, id: &str) -> db::models::Build {
+        db::models::Build {
+            id: 1,
+            source: source.to_string(),
+            version: String::from("1.0.0"),
+            champion_alias: alias.to_string(),
+            champion_id: id.to_string(),
+            content: Value::Null,
+        }
+    }
+
+    fn entry(source: &str, champion: &str) -> BatchEntry {
+        BatchEntry {
+            source: source.to_string(),
+            champion: champion.to_string(),
+        }
+    }
+
+    fn found_alias(resp: &BatchResponse, source: &str, champion: &str) -> Option<String> {
+        match &resp[source][champion] {
+            BatchResult::Found { build } => Some(build.champion_alias.clone()),
+            BatchResult::NotFound => None,
+        }
+    }
+
+    #[test]
+    fn modes_match_their_sources() {
+        let source = |is_aram, is_urf| db::models::Source {
+            id: 1,
+            name: String::from("OP.GG"),
+            source: String::from("op-gg"),
+            version: String::from("1.0.0"),
+            retired_at: None,
+            is_aram,
+            is_urf,
+        };
+
+        assert!(GameMode::Aram.matches(&source(true, false)));
+        assert!(!GameMode::Aram.matches(&source(false, true)));
+        assert!(GameMode::Urf.matches(&source(false, true)));
+        assert!(!GameMode::Urf.matches(&source(true, false)));
+        assert!(!GameMode::Aram.matches(&source(false, false)));
+        assert!(!GameMode::Urf.matches(&source(false, false)));
+    }
+
+    #[test]
+    fn sources_etag_depends_on_the_mode() {
+        let etag = |mode: Option<GameMode>| {
+            let resp = Validators::new((Some(1), Some("13.24.1"), mode)).not_modified();
+            resp.headers()[header::ETAG].clone()
+        };
+
+        assert_ne!(etag(None), etag(Some(GameMode::Aram)));
+        assert_ne!(etag(Some(GameMode::Aram)), etag(Some(GameMode::Urf)));
+        assert_eq!(etag(Some(GameMode::Urf)), etag(Some(GameMode::Urf)));
+    }
+
+    #[test]
+    fn asset_names_are_plain_png_names() {
+        assert_eq!(asset_name("MonkeyKing.png").ok(), Some("MonkeyKing"));
+        assert_eq!(asset_name("1001.png").ok(), Some("1001"));
+        for file in [
+            "Ahri",
+            ".png",
+            "../Ahri.png",
+            "Ahri.jpg",
+            "Kai'Sa.png",
+            "a.b.png",
+        ] {
+            assert!(asset_name(file).is_err(), "{file}");
+        }
+    }
+
+    #[test]
+    fn batch_matches_rows_by_source_and_alias_or_id() {
+        let cache = BuildCache::new(8, Duration::from_secs(60));
+        let entries = vec![
+            entry("op-gg", "Ahri"),
+            entry("op-gg", "1"),
+            entry("u-gg", "Ahri"),
+        ];
+        let rows = [row("op-gg", "Ahri", "103"), row("op-gg", "Annie", "1")];
+
+        let resp = answer_batch(&cache, cache.generation(), entries, vec![None; 3], &rows);
+        assert_eq!(found_alias(&resp, "op-gg", "Ahri").as_deref(), Some("Ahri"));
+        assert_eq!(found_alias(&resp, "op-gg", "1").as_deref(), Some("Annie"));
+        // a champion of another source is no match
+        assert_eq!(found_alias(&resp, "u-gg", "Ahri"), None);
+    }
+
+    #[test]
+    fn batch_fills_the_cache_and_prefers_it() {
+        let cache = BuildCache::new(8, Duration::from_secs(60));
+        let rows = [row("op-gg", "Ahri", "103")];
+        answer_batch(
+            &cache,
+            cache.generation(),
+            vec![entry("op-gg", "103")],
+            vec![None],
+            &rows,
+        );
+        let cached = cache.get(Lookup::Exact, "op-gg", "103");
+        assert_eq!(cached.map(|b| b.champion_alias).as_deref(), Some("Ahri"));
+        assert!(cache.get(Lookup::Resolved, "op-gg", "103").is_none());
+
+        let resp = answer_batch(
+            &cache,
+            cache.generation(),
+            vec![entry("op-gg", "Annie")],
+            vec![Some(row("op-gg", "Annie", "1"))],
+            &[],
+        );
+        assert_eq!(
+            found_alias(&resp, "op-gg", "Annie").as_deref(),
+            Some("Annie")
+        );
+    }
 }
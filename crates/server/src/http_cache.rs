@@ -0,0 +1,189 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config;
+
+/// `ETag`, `Last-Modified` and `Cache-Control` of a response, and the matching conditional checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validators {
+    etag: String,
+    last_modified: Option<SystemTime>,
+    cache_control: String,
+}
+
+impl Validators {
+    /// The `ETag` is a SHA-256 of `key` as JSON, which should cover everything the response
+    /// depends on. Unlike `DefaultHasher` it stays the same across restarts and Rust releases.
+    pub fn new<K: Serialize>(key: K) -> Self {
+        let key = serde_json::to_vec(&key).unwrap_or_default();
+        let digest = Sha256::digest(&key);
+
+        Self {
+            etag: format!("\"{}\"", hex::encode(&digest[..16])),
+            last_modified: None,
+            cache_control: config::cache_control(),
+        }
+    }
+
+    pub fn with_last_modified(mut self, last_modified: Option<SystemTime>) -> Self {
+        self.last_modified = last_modified;
+        self
+    }
+
+    pub fn with_cache_control(mut self, cache_control: String) -> Self {
+        self.cache_control = cache_control;
+        self
+    }
+
+    /// `If-None-Match` wins over `If-Modified-Since` when both are sent.
+    pub fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            return if_none_match.to_str().is_ok_and(|v| {
+                v.split(',').any(|tag| {
+                    let tag = tag.trim();
+                    tag == "*" || tag.trim_start_matches("W/") == self.etag
+                })
+            });
+        }
+
+        let if_modified_since = headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| httpdate::parse_http_date(v).ok());
+        match (if_modified_since, self.last_modified) {
+            (Some(since), Some(modified)) => unix_secs(modified) <= unix_secs(since),
+            _ => false,
+        }
+    }
+
+    pub fn not_modified(&self) -> Response {
+        self.respond(StatusCode::NOT_MODIFIED)
+    }
+
+    /// Answers with a `304` if the client's copy is fresh, with `resp` otherwise.
+    pub fn respond_to(&self, headers: &HeaderMap, resp: impl IntoResponse) -> Response {
+        if self.is_fresh(headers) {
+            self.not_modified()
+        } else {
+            self.respond(resp)
+        }
+    }
+
+    /// Attaches the validators to `resp`.
+    pub fn respond(&self, resp: impl IntoResponse) -> Response {
+        let mut resp = resp.into_response();
+        let headers = resp.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(last_modified) = self.last_modified {
+            if let Ok(v) = HeaderValue::from_str(&httpdate::fmt_http_date(last_modified)) {
+                headers.insert(header::LAST_MODIFIED, v);
+            }
+        }
+        if let Ok(cache_control) = HeaderValue::from_str(&self.cache_control) {
+            headers.insert(header::CACHE_CONTROL, cache_control);
+        }
+        resp
+    }
+}
+
+/// HTTP dates only have second precision.
+fn unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn etag_of(validators: &Validators) -> String {
+        let resp = validators.respond(());
+        resp.headers()[header::ETAG].to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn etag_follows_the_key() {
+        assert_eq!(
+            etag_of(&Validators::new("a")),
+            etag_of(&Validators::new("a"))
+        );
+        assert_ne!(
+            etag_of(&Validators::new("a")),
+            etag_of(&Validators::new("b"))
+        );
+        // clients keep ETags across restarts, so they must not depend on the process
+        assert_eq!(
+            etag_of(&Validators::new("a")),
+            "\"ac8d8342bbb2362d13f0a559a3621bb4\""
+        );
+    }
+
+    #[test]
+    fn fresh_when_etag_matches() {
+        let validators = Validators::new(("source", "13.1.0"));
+        let etag = etag_of(&validators);
+
+        assert!(validators.is_fresh(&headers(header::IF_NONE_MATCH, &etag)));
+        assert!(validators.is_fresh(&headers(header::IF_NONE_MATCH, &format!("W/{etag}"))));
+        assert!(validators.is_fresh(&headers(
+            header::IF_NONE_MATCH,
+            &format!("\"other\", {etag}")
+        )));
+        assert!(validators.is_fresh(&headers(header::IF_NONE_MATCH, "*")));
+        assert!(!validators.is_fresh(&headers(header::IF_NONE_MATCH, "\"other\"")));
+        assert!(!validators.is_fresh(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn fresh_when_not_modified_since() {
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let validators = Validators::new("key").with_last_modified(Some(modified));
+        let since = |t: SystemTime| headers(header::IF_MODIFIED_SINCE, &httpdate::fmt_http_date(t));
+
+        assert!(validators.is_fresh(&since(modified)));
+        // sub-second precision is lost in HTTP dates
+        let precise =
+            Validators::new("key").with_last_modified(Some(modified + Duration::from_millis(500)));
+        assert!(precise.is_fresh(&since(modified)));
+        assert!(!validators.is_fresh(&since(modified - Duration::from_secs(1))));
+        assert!(!Validators::new("key").is_fresh(&since(modified)));
+    }
+
+    #[test]
+    fn etag_wins_over_last_modified() {
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let validators = Validators::new("key").with_last_modified(Some(modified));
+        let mut headers = headers(header::IF_NONE_MATCH, "\"other\"");
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&httpdate::fmt_http_date(modified)).unwrap(),
+        );
+
+        assert!(!validators.is_fresh(&headers));
+    }
+
+    #[test]
+    fn not_modified_keeps_the_validators() {
+        let validators = Validators::new("key");
+        let resp = validators.not_modified();
+
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers()[header::ETAG], etag_of(&validators).as_str());
+        assert!(resp.headers().contains_key(header::CACHE_CONTROL));
+    }
+}
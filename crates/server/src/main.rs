@@ -1,4 +1,8 @@
-use axum::{extract::Extension, routing::get, Router};
+use axum::{
+    extract::Extension,
+    routing::{delete, get, post},
+    Router,
+};
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
 use dotenvy::dotenv;
 use tower_http::trace::{self, TraceLayer};
@@ -6,9 +10,16 @@ use tracing::Level;
 
 use std::{env, net::SocketAddr, time::Duration};
 
+pub mod assets;
+pub mod build_cache;
 pub mod config;
+pub mod ddragon;
 pub mod errors;
+pub mod events;
 pub mod handler;
+pub mod http_cache;
+pub mod snapshot;
+pub mod updates;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -31,6 +42,20 @@ async fn main() -> anyhow::Result<()> {
         .timeout_read(Duration::from_secs(10))
         .timeout_write(Duration::from_secs(10))
         .build();
+    let sync_events = events::SyncEvents::new();
+    sync_events.listen();
+
+    let updates = updates::Updates::new();
+    updates.watch(pool.clone(), &sync_events);
+
+    let snapshots = snapshot::Snapshots::new(pool.clone());
+
+    let ddragon = ddragon::DataDragon::new(pool.clone(), agent.clone());
+    ddragon.watch(&sync_events);
+    let assets = assets::AssetCache::new(config::assets_dir(), agent.clone());
+    let build_cache =
+        build_cache::BuildCache::new(config::BUILD_CACHE_CAPACITY, config::BUILD_CACHE_TTL);
+    build_cache.watch(pool.clone(), &sync_events, config::SYNC_POLL_INTERVAL);
 
     let api_routes = Router::new()
         .route("/sources", get(handler::list_sources))
@@ -50,10 +75,48 @@ async fn main() -> anyhow::Result<()> {
             "/source/:source/champion-id/:champion_id",
             get(handler::get_builds_by_champion_id),
         )
+        .route(
+            "/source/:source/rune-pages/:champion",
+            get(handler::get_rune_pages),
+        )
+        .route("/source/:source/diff/:champion", get(handler::diff_builds))
+        .route("/source/:source/item-sets", get(handler::export_item_sets))
+        .route("/changes", get(handler::list_changes))
+        .route("/snapshot/:source", get(handler::get_snapshot))
+        .route("/builds/batch", post(handler::get_builds_batch))
+        .route("/events", get(handler::stream_events))
+        .route(
+            "/admin/webhooks",
+            get(handler::list_webhooks).post(handler::create_webhook),
+        )
+        .route("/admin/webhooks/:id", delete(handler::delete_webhook))
+        .route(
+            "/admin/webhooks/:id/deliveries",
+            get(handler::list_webhook_deliveries),
+        )
+        .route("/cache/stats", get(handler::get_cache_stats))
+        .route("/champions/search", get(handler::search_champions))
+        .route("/consensus/:champion", get(handler::get_consensus))
+        .route("/compare/:champion", get(handler::compare_builds))
+        .route("/assets/champion/:file", get(handler::get_champion_icon))
+        .route("/assets/item/:file", get(handler::get_item_icon))
+        .route("/assets/perk/:file", get(handler::get_perk_icon))
+        .route("/data-dragon/versions", get(handler::list_versions))
         .route("/data-dragon/champions", get(handler::list_champion_map))
         .route("/data-dragon/runes", get(handler::list_runes_reforged))
+        .route("/data-dragon/items", get(handler::list_items))
+        .route(
+            "/data-dragon/summoner-spells",
+            get(handler::list_summoner_spells),
+        )
         .layer(Extension(pool))
-        .layer(Extension(agent));
+        .layer(Extension(agent))
+        .layer(Extension(ddragon))
+        .layer(Extension(assets))
+        .layer(Extension(build_cache))
+        .layer(Extension(sync_events))
+        .layer(Extension(updates))
+        .layer(Extension(snapshots));
 
     let app = Router::new().nest("/api", api_routes).layer(
         TraceLayer::new_for_http()
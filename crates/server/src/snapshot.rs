@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Cursor, Write},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Snapshots every source's builds at once.
+pub const ALL_SOURCES: &str = "all";
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SnapshotFormat {
+    #[default]
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl SnapshotFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SnapshotFormat::TarGz => "tar.gz",
+            SnapshotFormat::Zip => "zip",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SnapshotFormat::TarGz => "application/gzip",
+            SnapshotFormat::Zip => "application/zip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub source: String,
+    pub champion_alias: String,
+    pub champion_id: String,
+    pub version: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    /// continue with `/api/changes?since=<cursor>` to stay up to date
+    pub cursor: i64,
+    pub created_at: u64,
+    /// newest package version of each source
+    pub sources: BTreeMap<String, String>,
+    pub files: Vec<ManifestFile>,
+}
+
+pub struct Snapshot {
+    /// shared by every response instead of copied into each
+    pub body: Bytes,
+    /// of `body`
+    pub sha256: String,
+    pub cursor: i64,
+    pub created_at: SystemTime,
+}
+
+/// (source, format)
+type CacheKey = (String, SnapshotFormat);
+
+/// The latest snapshot of a key, locked while it's built.
+type Slot = Arc<tokio::sync::Mutex<Option<Arc<Snapshot>>>>;
+
+/// Archives of every champion's builds, built on the first request after each sync.
+#[derive(Clone)]
+pub struct Snapshots {
+    pool: db::DbPool,
+    cache: Arc<Mutex<HashMap<CacheKey, Slot>>>,
+}
+
+impl Snapshots {
+    pub fn new(pool: db::DbPool) -> Self {
+        Self {
+            pool,
+            cache: Default::default(),
+        }
+    }
+
+    /// The snapshot of a source, or of every source for `all`, as of the `cursor` build change.
+    /// `None` if there are no builds.
+    pub async fn get(
+        &self,
+        source: &str,
+        format: SnapshotFormat,
+        cursor: i64,
+    ) -> anyhow::Result<Option<Arc<Snapshot>>> {
+        let key = (source.to_string(), format);
+        let existing = self.cache.lock().unwrap().get(&key).cloned();
+        let slot = match existing {
+            Some(slot) => slot,
+            // only sources with builds get a slot, so requests of any name can't grow the map
+            None => {
+                let mut conn = db::get_conn(self.pool.clone()).await?;
+                if source != ALL_SOURCES
+                    && db::count_builds_by_source(&mut conn, source.to_string()).await? == 0
+                {
+                    return Ok(None);
+                }
+                let mut cache = self.cache.lock().unwrap();
+                cache.entry(key.clone()).or_default().clone()
+            }
+        };
+        // concurrent requests of a key wait for one build instead of each making one, while
+        // other keys are built at the same time
+        let mut snapshot = slot.lock().await;
+        if let Some(snapshot) = snapshot.as_ref().filter(|s| s.cursor == cursor) {
+            return Ok(Some(snapshot.clone()));
+        }
+
+        let mut conn = db::get_conn(self.pool.clone()).await?;
+        let rows = if source == ALL_SOURCES {
+            db::list_builds(&mut conn).await?
+        } else {
+            db::list_builds_by_source(&mut conn, source.to_string()).await?
+        };
+        if rows.is_empty() {
+            // e.g. a pruned source, whose old archive shouldn't stay in memory
+            *snapshot = None;
+            let mut cache = self.cache.lock().unwrap();
+            if cache.get(&key).is_some_and(|s| Arc::ptr_eq(s, &slot)) {
+                cache.remove(&key);
+            }
+            return Ok(None);
+        }
+
+        let built =
+            tokio::task::spawn_blocking(move || build_snapshot(rows, cursor, format)).await??;
+        let built = Arc::new(built);
+        *snapshot = Some(built.clone());
+        Ok(Some(built))
+    }
+}
+
+fn build_snapshot(
+    rows: Vec<db::models::Build>,
+    cursor: i64,
+    format: SnapshotFormat,
+) -> anyhow::Result<Snapshot> {
+    let created_at = SystemTime::now();
+    let mut manifest = Manifest {
+        cursor,
+        created_at: created_at.duration_since(UNIX_EPOCH)?.as_secs(),
+        sources: BTreeMap::new(),
+        files: vec![],
+    };
+
+    let mut files = vec![];
+    for row in rows {
+        let path = format!("{}/{}.json", row.source, row.champion_alias);
+        let content = serde_json::to_vec_pretty(&row)?;
+        let newer = manifest
+            .sources
+            .get(&row.source)
+            .is_none_or(|v| db::version_key(&row.version) > db::version_key(v));
+        if newer {
+            manifest
+                .sources
+                .insert(row.source.clone(), row.version.clone());
+        }
+        manifest.files.push(ManifestFile {
+            path: path.clone(),
+            source: row.source,
+            champion_alias: row.champion_alias,
+            champion_id: row.champion_id,
+            version: row.version,
+            sha256: sha256_hex(&content),
+        });
+        files.push((path, content));
+    }
+    files.push((
+        MANIFEST_FILE.to_string(),
+        serde_json::to_vec_pretty(&manifest)?,
+    ));
+
+    let body = match format {
+        SnapshotFormat::TarGz => tar_gz(&files, manifest.created_at)?,
+        SnapshotFormat::Zip => zip(&files)?,
+    };
+    Ok(Snapshot {
+        sha256: sha256_hex(&body),
+        body: Bytes::from(body),
+        cursor,
+        created_at,
+    })
+}
+
+fn tar_gz(files: &[(String, Vec<u8>)], mtime: u64) -> anyhow::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, path, content.as_slice())?;
+    }
+
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// `(path, content)` of each file.
+pub fn zip(files: &[(String, Vec<u8>)]) -> anyhow::Result<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default();
+    for (path, content) in files {
+        writer.start_file(path.as_str(), options)?;
+        writer.write_all(content)?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
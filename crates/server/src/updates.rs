@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use service::webhook::WebhookEvent;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{config, events::SyncEvents};
+
+/// source -> (version, champions)
+type SourceVersions = BTreeMap<String, (String, i64)>;
+
+/// What clients are told about, derived from syncs by comparing against what was there before.
+/// They're the events webhooks get, so both see the same payloads.
+#[derive(Clone)]
+pub struct Updates {
+    sender: broadcast::Sender<WebhookEvent>,
+}
+
+impl Updates {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(config::SYNC_EVENTS_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WebhookEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Remembers the current versions, then announces whatever each sync changed.
+    pub fn watch(&self, pool: db::DbPool, events: &SyncEvents) {
+        let sender = self.sender.clone();
+        // subscribed first, so syncs finishing while the versions are read aren't missed
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            let mut sources = source_versions(pool.clone()).await.unwrap_or_default();
+            let mut patch = latest_patch(pool.clone()).await.flatten();
+
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => Some(event),
+                    Err(RecvError::Lagged(_)) => None,
+                    Err(RecvError::Closed) => break,
+                };
+
+                if event.as_ref().is_none_or(|e| e.action == "sync_builds") {
+                    if let Some(current) = source_versions(pool.clone()).await {
+                        for update in source_updates(&sources, &current) {
+                            let _ = sender.send(update);
+                        }
+                        sources = current;
+                    }
+                }
+
+                if event.as_ref().is_none_or(|e| e.action == "sync_ddragon") {
+                    if let Some(Some(current)) = latest_patch(pool.clone()).await {
+                        if let Some(update) = patch_update(patch.take(), &current) {
+                            let _ = sender.send(update);
+                        }
+                        patch = Some(current);
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl Default for Updates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `SourceUpdated` for each source whose version differs from before, new sources included.
+fn source_updates(old: &SourceVersions, current: &SourceVersions) -> Vec<WebhookEvent> {
+    current
+        .iter()
+        .filter_map(|(source, (version, champions))| {
+            let old_version = old.get(source).map(|(v, _)| v.clone());
+            (old_version.as_ref() != Some(version)).then(|| WebhookEvent::SourceUpdated {
+                source: source.clone(),
+                old_version,
+                new_version: version.clone(),
+                champions: *champions,
+            })
+        })
+        .collect()
+}
+
+fn patch_update(old: Option<String>, current: &str) -> Option<WebhookEvent> {
+    (old.as_deref() != Some(current)).then(|| WebhookEvent::DdragonPatch {
+        old_version: old,
+        new_version: current.to_string(),
+    })
+}
+
+/// The newest package version of each source, `None` if they couldn't be read.
+async fn source_versions(pool: db::DbPool) -> Option<SourceVersions> {
+    match db::latest_source_versions(pool).await {
+        Ok(latest) => Some(
+            latest
+                .into_iter()
+                .map(|(source, v)| (source, (v.version, v.champions)))
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::warn!("failed to list source versions: {e}");
+            None
+        }
+    }
+}
+
+async fn latest_patch(pool: db::DbPool) -> Option<Option<String>> {
+    match db::list_ddragon_versions(pool).await {
+        Ok(versions) => Some(versions.into_iter().next()),
+        Err(e) => {
+            tracing::warn!("failed to list synced lol versions: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(entries: &[(&str, &str)]) -> SourceVersions {
+        entries
+            .iter()
+            .map(|(source, version)| (source.to_string(), (version.to_string(), 160)))
+            .collect()
+    }
+
+    #[test]
+    fn announces_changed_and_new_sources() {
+        let old = versions(&[("op-gg", "1.0.0"), ("u-gg", "2.0.0")]);
+        let current = versions(&[
+            ("mobalytics", "1.0.0"),
+            ("op-gg", "1.1.0"),
+            ("u-gg", "2.0.0"),
+        ]);
+
+        assert_eq!(
+            source_updates(&old, &current),
+            [
+                WebhookEvent::SourceUpdated {
+                    source: String::from("mobalytics"),
+                    old_version: None,
+                    new_version: String::from("1.0.0"),
+                    champions: 160,
+                },
+                WebhookEvent::SourceUpdated {
+                    source: String::from("op-gg"),
+                    old_version: Some(String::from("1.0.0")),
+                    new_version: String::from("1.1.0"),
+                    champions: 160,
+                },
+            ]
+        );
+        assert!(source_updates(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn announces_only_new_patches() {
+        assert_eq!(
+            patch_update(Some(String::from("13.23.1")), "13.24.1"),
+            Some(WebhookEvent::DdragonPatch {
+                old_version: Some(String::from("13.23.1")),
+                new_version: String::from("13.24.1"),
+            })
+        );
+        assert_eq!(patch_update(Some(String::from("13.24.1")), "13.24.1"), None);
+        assert!(patch_update(None, "13.24.1").is_some());
+    }
+}
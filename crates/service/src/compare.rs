@@ -0,0 +1,338 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_derive::Serialize;
+
+use crate::{filter::Position, Build, ChampionMapResp, ItemMapResp, RuneStyle};
+
+/// Display names from Data Dragon, keyed by champion id or key, perk id and item id.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct NameMap {
+    pub champions: HashMap<String, String>,
+    pub perks: HashMap<u64, String>,
+    pub items: HashMap<String, String>,
+}
+
+/// Names of only the champions, perks and items used by a set of builds.
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedNames {
+    pub champions: BTreeMap<String, String>,
+    pub perks: BTreeMap<u64, String>,
+    pub items: BTreeMap<String, String>,
+}
+
+impl NameMap {
+    pub fn new(
+        champions: &ChampionMapResp,
+        rune_styles: &[RuneStyle],
+        items: &ItemMapResp,
+    ) -> Self {
+        let champions = champions
+            .data
+            .values()
+            .flat_map(|c| {
+                [
+                    (c.id.clone(), c.name.clone()),
+                    (c.key.clone(), c.name.clone()),
+                ]
+            })
+            .collect();
+        let mut perks = HashMap::new();
+        for style in rune_styles {
+            perks.insert(style.id, style.name.clone());
+            for perk in style.slots.iter().flat_map(|s| s.runes.iter()) {
+                perks.insert(perk.id, perk.name.clone());
+            }
+        }
+        let items = items
+            .data
+            .iter()
+            .map(|(id, item)| (id.clone(), item.name.clone()))
+            .collect();
+
+        Self {
+            champions,
+            perks,
+            items,
+        }
+    }
+
+    pub fn localize(&self, builds: &[Build]) -> LocalizedNames {
+        let mut names = LocalizedNames::default();
+        for build in builds {
+            for champion in [&build.id, &build.alias] {
+                if let Some(name) = self.champions.get(champion) {
+                    names.champions.insert(champion.clone(), name.clone());
+                }
+            }
+            for rune in build.runes.iter() {
+                let ids = [rune.primary_style_id, rune.sub_style_id];
+                for id in ids.iter().chain(rune.selected_perk_ids.iter()) {
+                    if let Some(name) = self.perks.get(id) {
+                        names.perks.insert(*id, name.clone());
+                    }
+                }
+            }
+            let items = build
+                .item_builds
+                .iter()
+                .flat_map(|ib| ib.blocks.iter())
+                .flat_map(|block| block.items.iter().flatten());
+            for item in items {
+                if let Some(name) = self.items.get(&item.id) {
+                    names.items.insert(item.id.clone(), name.clone());
+                }
+            }
+        }
+
+        names
+    }
+
+    fn perk(&self, id: u64) -> Named<u64> {
+        Named {
+            id,
+            name: self.perks.get(&id).cloned(),
+        }
+    }
+
+    fn item(&self, id: String) -> Named<String> {
+        let name = self.items.get(&id).cloned();
+        Named { id, name }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Named<T> {
+    pub id: T,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedRunePage {
+    pub primary_style: Named<u64>,
+    pub sub_style: Named<u64>,
+    pub perks: Vec<Named<u64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedBlock {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub items: Vec<Named<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedSource {
+    pub source: String,
+    pub position: String,
+    pub rune_page: Option<ComparedRunePage>,
+    pub spells: Vec<String>,
+    pub skills: Vec<String>,
+    pub item_blocks: Vec<ComparedBlock>,
+}
+
+/// Choices shared by every compared source, and choices only a single source made.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Overlap<T> {
+    pub common: Vec<T>,
+    pub unique: BTreeMap<String, Vec<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub champion: String,
+    pub sources: Vec<ComparedSource>,
+    pub perks: Overlap<u64>,
+    pub spells: Overlap<String>,
+    pub skills: Overlap<Vec<String>>,
+    pub items: Overlap<String>,
+}
+
+/// Picks the first build of each source (matching `position` if given) and lines them up.
+pub fn compare_builds(
+    champion: String,
+    builds_by_source: Vec<(String, Vec<Build>)>,
+    position: Option<Position>,
+    names: &NameMap,
+) -> Comparison {
+    let picked = builds_by_source
+        .into_iter()
+        .filter_map(|(source, builds)| {
+            builds
+                .into_iter()
+                .find(|b| position.is_none_or(|p| p.matches(&b.position)))
+                .map(|b| (source, b))
+        })
+        .collect::<Vec<(String, Build)>>();
+
+    let perks = overlap(&picked, |b| {
+        b.runes
+            .first()
+            .map(|r| r.selected_perk_ids.clone())
+            .unwrap_or_default()
+    });
+    let spells = overlap(&picked, |b| b.spells.clone().unwrap_or_default());
+    let skills = overlap(&picked, |b| b.skills.clone().into_iter().collect());
+    let items = overlap(&picked, |b| {
+        b.item_builds
+            .iter()
+            .flat_map(|ib| ib.blocks.iter())
+            .flat_map(|block| block.items.iter().flatten())
+            .map(|i| i.id.clone())
+            .collect()
+    });
+
+    let sources = picked
+        .into_iter()
+        .map(|(source, b)| ComparedSource {
+            source,
+            position: b.position,
+            rune_page: b.runes.first().map(|r| ComparedRunePage {
+                primary_style: names.perk(r.primary_style_id),
+                sub_style: names.perk(r.sub_style_id),
+                perks: r
+                    .selected_perk_ids
+                    .iter()
+                    .map(|id| names.perk(*id))
+                    .collect(),
+            }),
+            spells: b.spells.unwrap_or_default(),
+            skills: b.skills.unwrap_or_default(),
+            item_blocks: b
+                .item_builds
+                .into_iter()
+                .flat_map(|ib| ib.blocks)
+                .map(|block| ComparedBlock {
+                    type_field: block.type_field,
+                    items: block
+                        .items
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|i| names.item(i.id))
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Comparison {
+        champion,
+        sources,
+        perks,
+        spells,
+        skills,
+        items,
+    }
+}
+
+fn overlap<T, F>(picked: &[(String, Build)], choices: F) -> Overlap<T>
+where
+    T: Ord + Clone,
+    F: Fn(&Build) -> Vec<T>,
+{
+    let sets = picked
+        .iter()
+        .map(|(source, b)| (source.clone(), choices(b).into_iter().collect()))
+        .collect::<Vec<(String, BTreeSet<T>)>>();
+
+    let mut counts = BTreeMap::new();
+    for (_, set) in sets.iter() {
+        for choice in set {
+            *counts.entry(choice.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let common = counts
+        .iter()
+        .filter(|(_, n)| **n == sets.len())
+        .map(|(choice, _)| choice.clone())
+        .collect();
+    let unique = sets
+        .into_iter()
+        .map(|(source, set)| {
+            let only_here = set.into_iter().filter(|c| counts[c] == 1).collect();
+            (source, only_here)
+        })
+        .collect();
+
+    Overlap { common, unique }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{block, build};
+
+    fn names() -> NameMap {
+        NameMap {
+            perks: HashMap::from([(8005, String::from("Press the Attack"))]),
+            items: HashMap::from([(String::from("1"), String::from("Long Sword"))]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn splits_common_and_unique_choices() {
+        let builds = vec![
+            (
+                "a".to_string(),
+                vec![build("top", vec![block("Core", &["1", "2"])], &[8005, 1])],
+            ),
+            (
+                "b".to_string(),
+                vec![build("top", vec![block("Core", &["1", "3"])], &[8005, 2])],
+            ),
+            (
+                "c".to_string(),
+                vec![build("top", vec![block("Core", &["1", "3"])], &[8005, 3])],
+            ),
+        ];
+        let comparison = compare_builds("Aatrox".to_string(), builds, None, &names());
+
+        assert_eq!(comparison.perks.common, [8005]);
+        assert_eq!(comparison.perks.unique["a"], [1]);
+        assert_eq!(comparison.items.common, ["1"]);
+        // picked by two of three sources, so neither common nor unique
+        assert_eq!(comparison.items.unique["a"], ["2"]);
+        assert!(comparison.items.unique["b"].is_empty());
+        assert_eq!(comparison.skills.common, [vec!["Q", "W"]]);
+    }
+
+    #[test]
+    fn picks_the_build_of_the_position() {
+        let builds = vec![
+            (
+                "a".to_string(),
+                vec![build("top", vec![], &[1]), build("mid", vec![], &[8005])],
+            ),
+            ("b".to_string(), vec![build("top", vec![], &[1])]),
+        ];
+        let comparison =
+            compare_builds("Aatrox".to_string(), builds, Some(Position::Mid), &names());
+
+        assert_eq!(comparison.sources.len(), 1);
+        let source = &comparison.sources[0];
+        assert_eq!(
+            (source.source.as_str(), source.position.as_str()),
+            ("a", "mid")
+        );
+        let perks = &source.rune_page.as_ref().unwrap().perks;
+        assert_eq!(perks[0].name.as_deref(), Some("Press the Attack"));
+    }
+
+    #[test]
+    fn localizes_only_the_names_in_use() {
+        let builds = [build("top", vec![block("Core", &["1", "9"])], &[8005])];
+        let localized = names().localize(&builds);
+
+        assert_eq!(localized.items.len(), 1);
+        assert_eq!(localized.items["1"], "Long Sword");
+        assert_eq!(localized.perks[&8005], "Press the Attack");
+    }
+}
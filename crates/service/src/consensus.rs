@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use serde_derive::Serialize;
+
+use crate::{filter::Position, Block, Build};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pick<T> {
+    pub value: T,
+    /// share of the total weight that voted for `value`, in percent
+    pub agreement: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Consensus {
+    pub champion: String,
+    pub sources: Vec<String>,
+    pub keystone: Option<Pick<u64>>,
+    pub primary_style_id: Option<Pick<u64>>,
+    pub sub_style_id: Option<Pick<u64>>,
+    pub selected_perk_ids: Option<Pick<Vec<u64>>>,
+    pub core_items: Option<Pick<Vec<String>>>,
+}
+
+/// Aggregates the builds of every source into the most agreed-upon runes and core items.
+/// Runes are weighted by their own `pick_count`, item blocks by the build's `pick_count`.
+pub fn build_consensus(
+    champion: String,
+    builds_by_source: Vec<(String, Vec<Build>)>,
+    position: Option<Position>,
+) -> Consensus {
+    let mut sources = vec![];
+    let mut keystones = vec![];
+    let mut primary_styles = vec![];
+    let mut sub_styles = vec![];
+    let mut perks = vec![];
+    let mut core_items = vec![];
+
+    for (source, builds) in builds_by_source {
+        let builds = builds
+            .into_iter()
+            .filter(|b| position.is_none_or(|p| p.matches(&b.position)))
+            .collect::<Vec<Build>>();
+        if builds.is_empty() {
+            continue;
+        }
+        sources.push(source);
+
+        for build in builds {
+            let build_weight = build.pick_count.max(1) as u64;
+
+            for rune in build.runes {
+                let weight = rune.pick_count.max(1);
+                if let Some(keystone) = rune.selected_perk_ids.first() {
+                    keystones.push((*keystone, weight));
+                }
+                primary_styles.push((rune.primary_style_id, weight));
+                sub_styles.push((rune.sub_style_id, weight));
+                perks.push((rune.selected_perk_ids, weight));
+            }
+
+            for item_build in build.item_builds {
+                let Some(block) = core_block(&item_build.blocks) else {
+                    continue;
+                };
+                let ids = block
+                    .items
+                    .iter()
+                    .flatten()
+                    .map(|i| i.id.clone())
+                    .collect::<Vec<String>>();
+                if !ids.is_empty() {
+                    core_items.push((ids, build_weight));
+                }
+            }
+        }
+    }
+
+    Consensus {
+        champion,
+        sources,
+        keystone: most_common(keystones),
+        primary_style_id: most_common(primary_styles),
+        sub_style_id: most_common(sub_styles),
+        selected_perk_ids: most_common(perks),
+        core_items: most_common(core_items),
+    }
+}
+
+/// The block titled as core, e.g. `Core Items`. Many sources don't name their blocks that way,
+/// so it falls back to position in the usual starter / early / core / situational layout:
+/// the third block, or the last one of shorter item sets.
+fn core_block(blocks: &[Block]) -> Option<&Block> {
+    blocks
+        .iter()
+        .find(|b| b.type_field.to_lowercase().contains("core"))
+        .or_else(|| blocks.get(CORE_BLOCK_INDEX).or(blocks.last()))
+}
+
+const CORE_BLOCK_INDEX: usize = 2;
+
+fn most_common<T: Ord>(votes: Vec<(T, u64)>) -> Option<Pick<T>> {
+    let mut tally = BTreeMap::new();
+    let mut total = 0;
+    for (value, weight) in votes {
+        *tally.entry(value).or_insert(0) += weight;
+        total += weight;
+    }
+
+    tally
+        .into_iter()
+        .max_by_key(|(_, weight)| *weight)
+        .map(|(value, weight)| Pick {
+            value,
+            agreement: weight as f64 * 100.0 / total as f64,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, block};
+
+    /// A build voted for by `pick_count` games.
+    fn build(position: &str, pick_count: i64, blocks: Vec<Block>, perks: &[u64]) -> Build {
+        let mut build = fixtures::build(position, blocks, perks);
+        build.pick_count = pick_count;
+        build.runes[0].pick_count = pick_count as u64;
+        build
+    }
+
+    #[test]
+    fn picks_the_heaviest_vote() {
+        let builds = vec![
+            (
+                "a".to_string(),
+                vec![build("top", 100, vec![block("Core", &["1", "2"])], &[8005])],
+            ),
+            (
+                "b".to_string(),
+                vec![build("top", 300, vec![block("Core", &["1", "3"])], &[8010])],
+            ),
+        ];
+        let consensus = build_consensus("Aatrox".to_string(), builds, None);
+
+        assert_eq!(consensus.sources, ["a", "b"]);
+        let keystone = consensus.keystone.unwrap();
+        assert_eq!(keystone.value, 8010);
+        assert_eq!(keystone.agreement, 75.0);
+        assert_eq!(consensus.core_items.unwrap().value, ["1", "3"]);
+    }
+
+    #[test]
+    fn skips_sources_without_the_position() {
+        let builds = vec![
+            ("a".to_string(), vec![build("top", 1, vec![], &[8005])]),
+            ("b".to_string(), vec![build("mid", 1, vec![], &[8010])]),
+        ];
+        let consensus = build_consensus("Aatrox".to_string(), builds, Some(Position::Mid));
+
+        assert_eq!(consensus.sources, ["b"]);
+        assert_eq!(consensus.keystone.unwrap().value, 8010);
+    }
+
+    #[test]
+    fn finds_core_block_by_title_then_position() {
+        let titled = [block("Starter", &["1"]), block("core items", &["2"])];
+        assert_eq!(core_block(&titled).unwrap().type_field, "core items");
+
+        let untitled = [
+            block("Starter", &["1"]),
+            block("Early", &["2"]),
+            block("Mid game", &["3"]),
+            block("Situational", &["4"]),
+        ];
+        assert_eq!(core_block(&untitled).unwrap().type_field, "Mid game");
+
+        let short = [block("Starter", &["1"]), block("Full build", &["2"])];
+        assert_eq!(core_block(&short).unwrap().type_field, "Full build");
+
+        assert!(core_block(&[]).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeSet;
+
+use serde_derive::Serialize;
+
+use crate::{Block, Build, Rune};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunePage {
+    pub primary_style_id: u64,
+    pub sub_style_id: u64,
+    pub selected_perk_ids: Vec<u64>,
+}
+
+impl From<&Rune> for RunePage {
+    fn from(rune: &Rune) -> Self {
+        Self {
+            primary_style_id: rune.primary_style_id,
+            sub_style_id: rune.sub_style_id,
+            selected_perk_ids: rune.selected_perk_ids.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum BlockDiff {
+    Added {
+        #[serde(rename = "type")]
+        type_field: String,
+        items: Vec<String>,
+    },
+    Removed {
+        #[serde(rename = "type")]
+        type_field: String,
+        items: Vec<String>,
+    },
+    /// same items, different order
+    Reordered {
+        #[serde(rename = "type")]
+        type_field: String,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    Changed {
+        #[serde(rename = "type")]
+        type_field: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionDiff {
+    pub position: String,
+    pub runes_added: Vec<RunePage>,
+    pub runes_removed: Vec<RunePage>,
+    pub block_order: Option<Change<Vec<String>>>,
+    pub item_blocks: Vec<BlockDiff>,
+    pub skills: Option<Change<Vec<String>>>,
+    pub spells: Option<Change<Vec<String>>>,
+}
+
+impl PositionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.runes_added.is_empty()
+            && self.runes_removed.is_empty()
+            && self.block_order.is_none()
+            && self.item_blocks.is_empty()
+            && self.skills.is_none()
+            && self.spells.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChampionDiff {
+    pub positions_added: Vec<String>,
+    pub positions_removed: Vec<String>,
+    pub changed: Vec<PositionDiff>,
+}
+
+/// Compares two versions of a champion's builds, matching builds by position.
+pub fn diff_builds(from: &[Build], to: &[Build]) -> ChampionDiff {
+    let positions_added = to
+        .iter()
+        .filter(|b| !from.iter().any(|f| f.position == b.position))
+        .map(|b| b.position.clone())
+        .collect();
+    let positions_removed = from
+        .iter()
+        .filter(|b| !to.iter().any(|t| t.position == b.position))
+        .map(|b| b.position.clone())
+        .collect();
+    let changed = from
+        .iter()
+        .filter_map(|f| {
+            to.iter()
+                .find(|t| t.position == f.position)
+                .map(|t| diff_build(f, t))
+        })
+        .filter(|d| !d.is_empty())
+        .collect();
+
+    ChampionDiff {
+        positions_added,
+        positions_removed,
+        changed,
+    }
+}
+
+fn diff_build(from: &Build, to: &Build) -> PositionDiff {
+    let from_runes = from
+        .runes
+        .iter()
+        .map(RunePage::from)
+        .collect::<BTreeSet<_>>();
+    let to_runes = to.runes.iter().map(RunePage::from).collect::<BTreeSet<_>>();
+
+    let from_blocks = flatten_blocks(from);
+    let to_blocks = flatten_blocks(to);
+    let from_order = from_blocks.iter().map(|b| b.type_field.clone()).collect();
+    let to_order = to_blocks.iter().map(|b| b.type_field.clone()).collect();
+
+    PositionDiff {
+        position: from.position.clone(),
+        runes_added: to_runes.difference(&from_runes).cloned().collect(),
+        runes_removed: from_runes.difference(&to_runes).cloned().collect(),
+        block_order: change(from_order, to_order),
+        item_blocks: diff_blocks(&from_blocks, &to_blocks),
+        skills: change(
+            from.skills.clone().unwrap_or_default(),
+            to.skills.clone().unwrap_or_default(),
+        ),
+        spells: change(
+            from.spells.clone().unwrap_or_default(),
+            to.spells.clone().unwrap_or_default(),
+        ),
+    }
+}
+
+fn flatten_blocks(build: &Build) -> Vec<&Block> {
+    build
+        .item_builds
+        .iter()
+        .flat_map(|ib| ib.blocks.iter())
+        .collect()
+}
+
+fn item_ids(block: &Block) -> Vec<String> {
+    block.items.iter().flatten().map(|i| i.id.clone()).collect()
+}
+
+fn diff_blocks(from: &[&Block], to: &[&Block]) -> Vec<BlockDiff> {
+    let mut diffs = vec![];
+
+    for f in from {
+        let from_items = item_ids(f);
+        let Some(t) = to.iter().find(|t| t.type_field == f.type_field) else {
+            diffs.push(BlockDiff::Removed {
+                type_field: f.type_field.clone(),
+                items: from_items,
+            });
+            continue;
+        };
+
+        let to_items = item_ids(t);
+        if from_items == to_items {
+            continue;
+        }
+        let from_set = from_items.iter().cloned().collect::<BTreeSet<String>>();
+        let to_set = to_items.iter().cloned().collect::<BTreeSet<String>>();
+        if from_set == to_set {
+            diffs.push(BlockDiff::Reordered {
+                type_field: f.type_field.clone(),
+                from: from_items,
+                to: to_items,
+            });
+        } else {
+            diffs.push(BlockDiff::Changed {
+                type_field: f.type_field.clone(),
+                added: to_set.difference(&from_set).cloned().collect(),
+                removed: from_set.difference(&to_set).cloned().collect(),
+            });
+        }
+    }
+
+    for t in to {
+        if !from.iter().any(|f| f.type_field == t.type_field) {
+            diffs.push(BlockDiff::Added {
+                type_field: t.type_field.clone(),
+                items: item_ids(t),
+            });
+        }
+    }
+
+    diffs
+}
+
+fn change<T: PartialEq>(from: T, to: T) -> Option<Change<T>> {
+    if from == to {
+        None
+    } else {
+        Some(Change { from, to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{block, build};
+
+    #[test]
+    fn same_builds_have_no_diff() {
+        let builds = vec![build("top", vec![block("Core", &["1", "2"])], &[1, 2])];
+        let diff = diff_builds(&builds, &builds);
+
+        assert!(diff.positions_added.is_empty());
+        assert!(diff.positions_removed.is_empty());
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn matches_builds_by_position() {
+        let from = vec![build("top", vec![], &[1]), build("mid", vec![], &[1])];
+        let to = vec![build("mid", vec![], &[1]), build("jungle", vec![], &[1])];
+        let diff = diff_builds(&from, &to);
+
+        assert_eq!(diff.positions_added, ["jungle"]);
+        assert_eq!(diff.positions_removed, ["top"]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn diffs_runes_blocks_and_skills() {
+        let from = vec![build(
+            "top",
+            vec![
+                block("Starter", &["1", "2"]),
+                block("Core", &["3", "4"]),
+                block("Boots", &["5"]),
+            ],
+            &[1, 2],
+        )];
+        let mut to = vec![build(
+            "top",
+            vec![
+                block("Starter", &["2", "1"]),
+                block("Core", &["3", "6"]),
+                block("Situational", &["7"]),
+            ],
+            &[1, 3],
+        )];
+        to[0].skills = Some(vec!["W".to_string(), "Q".to_string()]);
+
+        let diff = diff_builds(&from, &to);
+        assert_eq!(diff.changed.len(), 1);
+        let changed = &diff.changed[0];
+        assert_eq!(changed.runes_added[0].selected_perk_ids, [1, 3]);
+        assert_eq!(changed.runes_removed[0].selected_perk_ids, [1, 2]);
+        assert_eq!(
+            changed.item_blocks,
+            [
+                BlockDiff::Reordered {
+                    type_field: "Starter".to_string(),
+                    from: vec!["1".to_string(), "2".to_string()],
+                    to: vec!["2".to_string(), "1".to_string()],
+                },
+                BlockDiff::Changed {
+                    type_field: "Core".to_string(),
+                    added: vec!["6".to_string()],
+                    removed: vec!["4".to_string()],
+                },
+                BlockDiff::Removed {
+                    type_field: "Boots".to_string(),
+                    items: vec!["5".to_string()],
+                },
+                BlockDiff::Added {
+                    type_field: "Situational".to_string(),
+                    items: vec!["7".to_string()],
+                },
+            ]
+        );
+        assert!(changed.block_order.is_some());
+        assert_eq!(
+            changed.skills.as_ref().map(|s| s.to.clone()),
+            Some(vec!["W".to_string(), "Q".to_string()])
+        );
+        assert_eq!(changed.spells, None);
+    }
+}
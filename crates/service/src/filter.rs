@@ -0,0 +1,277 @@
+use serde_derive::Deserialize;
+
+use crate::{Build, Rune};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    Top,
+    Jungle,
+    Mid,
+    Adc,
+    Support,
+}
+
+impl Position {
+    /// Sources don't agree on lane names, e.g. `middle`/`bottom`/`utility`.
+    pub fn matches(&self, position: &str) -> bool {
+        let position = position.to_lowercase();
+        match self {
+            Self::Top => position == "top",
+            Self::Jungle => position == "jungle",
+            Self::Mid => matches!(position.as_str(), "mid" | "middle"),
+            Self::Adc => matches!(position.as_str(), "adc" | "bottom" | "bot"),
+            Self::Support => matches!(position.as_str(), "support" | "utility" | "sup"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    WinRate,
+    PickCount,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BuildFilter {
+    pub position: Option<Position>,
+    pub sort: Option<SortBy>,
+    pub limit: Option<usize>,
+}
+
+impl BuildFilter {
+    pub fn is_empty(&self) -> bool {
+        self.position.is_none() && self.sort.is_none() && self.limit.is_none()
+    }
+
+    /// Applies position, sort and limit to the builds and to the runes of each build.
+    /// With a position set, runes without one are dropped along with those of other positions.
+    pub fn apply(&self, builds: Vec<Build>) -> Vec<Build> {
+        let builds = builds
+            .into_iter()
+            .map(|mut b| {
+                b.runes = self.apply_to(b.runes);
+                b
+            })
+            .collect();
+        self.apply_to(builds)
+    }
+
+    fn apply_to<T: Ranked>(&self, list: Vec<T>) -> Vec<T> {
+        let mut list = list
+            .into_iter()
+            .filter(|i| self.matches_position(i.position()))
+            .collect::<Vec<T>>();
+
+        match self.sort {
+            Some(SortBy::WinRate) => list.sort_by(|a, b| {
+                parse_win_rate(b.win_rate()).total_cmp(&parse_win_rate(a.win_rate()))
+            }),
+            Some(SortBy::PickCount) => list.sort_by_key(|i| std::cmp::Reverse(i.pick_count())),
+            None => {}
+        }
+        if let Some(limit) = self.limit {
+            list.truncate(limit);
+        }
+
+        list
+    }
+
+    fn matches_position(&self, position: &str) -> bool {
+        match self.position {
+            Some(p) => p.matches(position),
+            None => true,
+        }
+    }
+}
+
+/// Win rates come as either `"51.2%"` or `"0.512"`, unparsable values rank last.
+pub fn parse_win_rate(win_rate: &str) -> f64 {
+    let trimmed = win_rate.trim();
+    match trimmed.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|v| v / 100.0),
+        None => trimmed.parse::<f64>(),
+    }
+    .unwrap_or(f64::MIN)
+}
+
+trait Ranked {
+    fn position(&self) -> &str;
+    fn win_rate(&self) -> &str;
+    fn pick_count(&self) -> u64;
+}
+
+impl Ranked for Build {
+    fn position(&self) -> &str {
+        &self.position
+    }
+
+    fn win_rate(&self) -> &str {
+        &self.win_rate
+    }
+
+    fn pick_count(&self) -> u64 {
+        self.pick_count.max(0) as u64
+    }
+}
+
+impl Ranked for Rune {
+    fn position(&self) -> &str {
+        &self.position
+    }
+
+    fn win_rate(&self) -> &str {
+        &self.win_rate
+    }
+
+    fn pick_count(&self) -> u64 {
+        self.pick_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(position: &str, win_rate: &str, pick_count: i64, runes: Vec<Rune>) -> Build {
+        Build {
+            position: position.to_string(),
+            win_rate: win_rate.to_string(),
+            pick_count,
+            runes,
+            ..Default::default()
+        }
+    }
+
+    fn rune(alias: &str, position: &str, win_rate: &str, pick_count: u64) -> Rune {
+        Rune {
+            alias: alias.to_string(),
+            position: position.to_string(),
+            win_rate: win_rate.to_string(),
+            pick_count,
+            ..Default::default()
+        }
+    }
+
+    fn positions(builds: &[Build]) -> Vec<&str> {
+        builds.iter().map(|b| b.position.as_str()).collect()
+    }
+
+    #[test]
+    fn empty_filter_keeps_everything() {
+        let builds = vec![
+            build("top", "50%", 1, vec![rune("a", "", "50%", 1)]),
+            build("mid", "51%", 2, vec![]),
+        ];
+        let filter = BuildFilter::default();
+
+        assert!(filter.is_empty());
+        assert_eq!(filter.apply(builds.clone()), builds);
+    }
+
+    #[test]
+    fn position_matches_lane_aliases() {
+        let builds = vec![
+            build("top", "50%", 1, vec![]),
+            build("middle", "50%", 1, vec![]),
+            build("BOTTOM", "50%", 1, vec![]),
+            build("utility", "50%", 1, vec![]),
+        ];
+
+        let filter = |position| BuildFilter {
+            position: Some(position),
+            ..Default::default()
+        };
+        assert_eq!(
+            positions(&filter(Position::Mid).apply(builds.clone())),
+            ["middle"]
+        );
+        assert_eq!(
+            positions(&filter(Position::Adc).apply(builds.clone())),
+            ["BOTTOM"]
+        );
+        assert_eq!(
+            positions(&filter(Position::Support).apply(builds.clone())),
+            ["utility"]
+        );
+        assert!(filter(Position::Jungle).apply(builds).is_empty());
+    }
+
+    #[test]
+    fn position_drops_runes_without_a_position() {
+        let builds = vec![build(
+            "top",
+            "50%",
+            1,
+            vec![
+                rune("top", "top", "50%", 1),
+                rune("any", "", "60%", 1),
+                rune("mid", "mid", "55%", 1),
+            ],
+        )];
+        let filter = BuildFilter {
+            position: Some(Position::Top),
+            ..Default::default()
+        };
+
+        let filtered = filter.apply(builds);
+        let aliases = filtered[0]
+            .runes
+            .iter()
+            .map(|r| r.alias.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(aliases, ["top"]);
+    }
+
+    #[test]
+    fn sorts_by_win_rate_then_limits() {
+        let builds = vec![
+            build("top", "0.49", 1, vec![]),
+            build("mid", "52.5%", 1, vec![]),
+            build("adc", "not a rate", 1, vec![]),
+            build("jungle", "51%", 1, vec![]),
+        ];
+        let filter = BuildFilter {
+            sort: Some(SortBy::WinRate),
+            limit: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(positions(&filter.apply(builds)), ["mid", "jungle", "top"]);
+    }
+
+    #[test]
+    fn sorts_runes_by_pick_count() {
+        let builds = vec![build(
+            "top",
+            "50%",
+            1,
+            vec![
+                rune("few", "top", "50%", 10),
+                rune("most", "top", "50%", 300),
+                rune("some", "top", "50%", 20),
+            ],
+        )];
+        let filter = BuildFilter {
+            sort: Some(SortBy::PickCount),
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        let filtered = filter.apply(builds);
+        let aliases = filtered[0]
+            .runes
+            .iter()
+            .map(|r| r.alias.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(aliases, ["most", "some"]);
+    }
+
+    #[test]
+    fn parses_win_rates() {
+        assert_eq!(parse_win_rate("51.2%"), 0.512);
+        assert_eq!(parse_win_rate(" 0.5 "), 0.5);
+        assert_eq!(parse_win_rate(""), f64::MIN);
+    }
+}
@@ -0,0 +1,74 @@
+//! Builds shared by the tests of the modules that take them apart.
+
+use crate::{Block, Build, Item, ItemBuild, Perk, Rune, RuneSlot, RuneStyle};
+
+/// A block of one of each item.
+pub fn block(type_field: &str, items: &[&str]) -> Block {
+    Block {
+        type_field: type_field.to_string(),
+        items: Some(
+            items
+                .iter()
+                .map(|id| Item {
+                    id: id.to_string(),
+                    count: 1,
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// A build of a single item build and rune page, Precision with Domination.
+pub fn build(position: &str, blocks: Vec<Block>, perks: &[u64]) -> Build {
+    Build {
+        position: position.to_string(),
+        item_builds: vec![ItemBuild {
+            blocks,
+            ..Default::default()
+        }],
+        runes: vec![Rune {
+            primary_style_id: 8000,
+            sub_style_id: 8100,
+            selected_perk_ids: perks.to_vec(),
+            ..Default::default()
+        }],
+        skills: Some(vec!["Q".to_string(), "W".to_string()]),
+        ..Default::default()
+    }
+}
+
+fn rune_style(id: u64, slots: &[&[u64]]) -> RuneStyle {
+    RuneStyle {
+        id,
+        slots: slots
+            .iter()
+            .map(|perks| RuneSlot {
+                runes: perks
+                    .iter()
+                    .map(|id| Perk {
+                        id: *id,
+                        ..Default::default()
+                    })
+                    .collect(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Precision and Domination, with two perks in each slot.
+pub fn rune_styles() -> Vec<RuneStyle> {
+    vec![
+        rune_style(
+            8000,
+            &[&[8005, 8008], &[9101, 9111], &[9104, 9105], &[8014, 8017]],
+        ),
+        rune_style(
+            8100,
+            &[&[8112, 8124], &[8126, 8139], &[8138, 8120], &[8135, 8105]],
+        ),
+    ]
+}
+
+/// A valid page of `rune_styles`, stat shards included.
+pub const PERKS: [u64; 9] = [8005, 9111, 9104, 8014, 8126, 8135, 5008, 5008, 5011];
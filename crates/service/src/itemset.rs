@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::{Build, ItemBuild};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemSetFile {
+    /// relative to the League of Legends install dir, always `/` separated
+    pub path: String,
+    pub item_set: ItemBuild,
+}
+
+/// Lays out every item build as `Config/Champions/<Alias>/Recommended/<source>-<position>-<n>.json`.
+pub fn item_set_files(source: &str, builds: &[Build]) -> Vec<ItemSetFile> {
+    let source = sanitize(source);
+
+    builds
+        .iter()
+        .flat_map(|build| {
+            let alias = sanitize(&build.alias);
+            let position = sanitize(&build.position);
+            let source = source.clone();
+
+            build
+                .item_builds
+                .iter()
+                .enumerate()
+                .map(move |(idx, item_build)| {
+                    let mut item_set = item_build.clone();
+                    if item_set.title.is_empty() {
+                        item_set.title = format!("{} {} {}", source, alias, position);
+                    }
+                    ItemSetFile {
+                        path: format!(
+                            "Config/Champions/{alias}/Recommended/{source}-{position}-{}.json",
+                            idx + 1
+                        ),
+                        item_set,
+                    }
+                })
+        })
+        .collect()
+}
+
+pub fn write_item_sets(out_dir: &str, files: &[ItemSetFile]) -> anyhow::Result<()> {
+    for file in files {
+        let path = Path::new(out_dir).join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+        let content = serde_json::to_vec_pretty(&file.item_set)?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Keeps a path segment from naming anything but a file of its own, e.g. `..` becomes `__`.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{block, build};
+
+    fn ahri(position: &str, titles: &[&str]) -> Build {
+        let mut build = build(position, vec![], &[]);
+        build.alias = String::from("Ahri");
+        build.item_builds = titles
+            .iter()
+            .map(|title| ItemBuild {
+                title: title.to_string(),
+                blocks: vec![block("Core", &["1"])],
+                ..Default::default()
+            })
+            .collect();
+        build
+    }
+
+    fn paths(files: &[ItemSetFile]) -> Vec<&str> {
+        files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn numbers_item_sets_per_champion_and_position() {
+        let files = item_set_files("op-gg", &[ahri("mid", &["", ""]), ahri("top", &[""])]);
+
+        assert_eq!(
+            paths(&files),
+            [
+                "Config/Champions/Ahri/Recommended/op-gg-mid-1.json",
+                "Config/Champions/Ahri/Recommended/op-gg-mid-2.json",
+                "Config/Champions/Ahri/Recommended/op-gg-top-1.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn titles_untitled_item_sets() {
+        let files = item_set_files("op-gg", &[ahri("mid", &["", "Full build"])]);
+
+        assert_eq!(files[0].item_set.title, "op-gg Ahri mid");
+        assert_eq!(files[1].item_set.title, "Full build");
+    }
+
+    #[test]
+    fn keeps_every_segment_in_its_dir() {
+        let mut build = ahri("../..", &[""]);
+        build.alias = String::from("..");
+        let files = item_set_files("op.gg/x", &[build]);
+
+        assert_eq!(
+            paths(&files),
+            ["Config/Champions/__/Recommended/op_gg_x-_____-1.json"]
+        );
+    }
+}
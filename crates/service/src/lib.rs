@@ -1,5 +1,19 @@
+pub mod compare;
+pub mod consensus;
+pub mod diff;
+pub mod filter;
+#[cfg(test)]
+mod fixtures;
+pub mod itemset;
+pub mod patch;
+pub mod resolve;
+pub mod rune_page;
+pub mod validate;
+pub mod webhook;
+
 use std::collections::HashMap;
 use std::io::{self, Cursor};
+use std::time::Duration;
 
 use anyhow::Context;
 use flate2::read::GzDecoder;
@@ -57,16 +71,102 @@ pub struct Champion {
     pub title: String,
 }
 
-pub async fn list_all_champions() -> Result<ChampionMapResp, reqwest::Error> {
+pub const DEFAULT_LOCALE: &str = "en_US";
+
+pub async fn list_languages() -> Result<Vec<String>, reqwest::Error> {
+    let r = reqwest::get("https://ddragon.leagueoflegends.com/cdn/languages.json").await?;
+    r.json::<Vec<String>>().await
+}
+
+pub async fn list_all_champions(locale: &str) -> Result<ChampionMapResp, reqwest::Error> {
     let version = get_latest_version().await?;
 
     let r = reqwest::get(format!(
-        "http://ddragon.leagueoflegends.com/cdn/{version}/data/en_US/champion.json"
+        "http://ddragon.leagueoflegends.com/cdn/{version}/data/{locale}/champion.json"
     ))
     .await?;
     r.json::<ChampionMapResp>().await
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuneStyle {
+    pub id: u64,
+    pub key: String,
+    pub icon: String,
+    pub name: String,
+    pub slots: Vec<RuneSlot>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuneSlot {
+    pub runes: Vec<Perk>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Perk {
+    pub id: u64,
+    pub key: String,
+    pub icon: String,
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemMapResp {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub version: String,
+    pub data: HashMap<String, ItemData>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemData {
+    pub name: String,
+}
+
+/// Data Dragon files stored by `sync-ddragon`.
+pub const DATA_DRAGON_FILES: [&str; 4] = [
+    "champion.json",
+    "item.json",
+    "runesReforged.json",
+    "summoner.json",
+];
+
+pub async fn get_data_dragon_file(
+    version: &str,
+    locale: &str,
+    file: &str,
+) -> Result<Value, reqwest::Error> {
+    let r = reqwest::get(format!(
+        "https://ddragon.leagueoflegends.com/cdn/{version}/data/{locale}/{file}"
+    ))
+    .await?;
+    r.json::<Value>().await
+}
+
+pub async fn list_runes_reforged(
+    version: &str,
+    locale: &str,
+) -> Result<Vec<RuneStyle>, reqwest::Error> {
+    let r = reqwest::get(format!(
+        "https://ddragon.leagueoflegends.com/cdn/{version}/data/{locale}/runesReforged.json"
+    ))
+    .await?;
+    r.json::<Vec<RuneStyle>>().await
+}
+
+pub async fn list_items(version: &str, locale: &str) -> Result<ItemMapResp, reqwest::Error> {
+    let r = reqwest::get(format!(
+        "https://ddragon.leagueoflegends.com/cdn/{version}/data/{locale}/item.json"
+    ))
+    .await?;
+    r.json::<ItemMapResp>().await
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Dist {
@@ -109,6 +209,24 @@ pub async fn download_and_extract_tgz(url: &str, output_dir: &str) -> io::Result
     Ok(())
 }
 
+/// Extracts a package tarball already on disk, e.g. one made by `npm pack`.
+pub fn extract_tgz(path: &str, output_dir: &str) -> io::Result<()> {
+    let gz = GzDecoder::new(std::fs::File::open(path)?);
+    let mut archive = Archive::new(gz);
+    archive.unpack(output_dir)?;
+
+    Ok(())
+}
+
+/// Reads the `version` of the `package.json` in an extracted package.
+pub async fn read_local_package_version(package_dir: &str) -> anyhow::Result<String> {
+    let package = read_local_build_file(format!("{package_dir}/package.json")).await?;
+    package["version"]
+        .as_str()
+        .map(String::from)
+        .with_context(|| format!("No version in {package_dir}/package.json"))
+}
+
 pub async fn read_local_build_file(file_path: String) -> anyhow::Result<Value> {
     let mut file = File::open(&file_path)
         .await
@@ -231,12 +349,80 @@ pub fn empty_rune_type() -> String {
     String::new()
 }
 
+pub const CDN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A champion's builds in a package version of a source,
+/// `None` if the version or the champion doesn't exist.
 pub async fn get_champion_build(
     champion: String,
     source: String,
     version: String,
-) -> Result<Vec<Build>, reqwest::Error> {
+) -> Result<Option<Vec<Build>>, reqwest::Error> {
     let url = format!("https://cdn.jsdelivr.net/npm/@champ-r/{source}@{version}/{champion}.json");
-    let resp = reqwest::get(&url).await?;
-    resp.json::<Vec<Build>>().await
+    let resp = reqwest::Client::new()
+        .get(&url)
+        .timeout(CDN_TIMEOUT)
+        .send()
+        .await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let builds = resp.error_for_status()?.json::<Vec<Build>>().await?;
+    Ok(Some(builds))
+}
+
+/// Source names and champion aliases, e.g. `op-gg-aram` or `MonkeyKing`, safe to put in a URL path.
+pub fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 64
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A semver package version like `1.2.3` or `1.2.3-beta.1`.
+pub fn is_package_version(v: &str) -> bool {
+    let (core, pre_release) = match v.split_once('-') {
+        Some((core, pre_release)) => (core, Some(pre_release)),
+        None => (v, None),
+    };
+    let core_ok = core.split('.').count() == 3
+        && core
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    let pre_release_ok = pre_release.is_none_or(|p| {
+        p.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+    });
+    core_ok && pre_release_ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_identifiers() {
+        assert!(is_identifier("op-gg-aram"));
+        assert!(is_identifier("MonkeyKing"));
+        assert!(is_identifier("lol_gg"));
+        assert!(!is_identifier(""));
+        assert!(!is_identifier(".."));
+        assert!(!is_identifier("a/b"));
+        assert!(!is_identifier("a@1.0.0"));
+        assert!(!is_identifier(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn accepts_semver_versions() {
+        assert!(is_package_version("1.2.3"));
+        assert!(is_package_version("10.0.12"));
+        assert!(is_package_version("1.2.3-beta.1"));
+        assert!(!is_package_version("1.2"));
+        assert!(!is_package_version("1.2.3.4"));
+        assert!(!is_package_version("latest"));
+        assert!(!is_package_version("1.2.3-"));
+        assert!(!is_package_version("1.2.3/../x"));
+        assert!(!is_package_version("1.2.x"));
+    }
 }
@@ -0,0 +1,64 @@
+/// Trims a version down to its patch, e.g. `13.22.1` to `13.22`.
+pub fn patch_of(version: &str) -> &str {
+    match version.match_indices('.').nth(1) {
+        Some((idx, _)) => &version[..idx],
+        None => version,
+    }
+}
+
+/// How many patches `official_version` is behind the newest of `versions` (newest first),
+/// `None` if it isn't a known patch.
+pub fn patch_lag(versions: &[String], official_version: &str) -> Option<usize> {
+    let target = patch_of(official_version);
+
+    let mut patches: Vec<&str> = vec![];
+    for v in versions {
+        let patch = patch_of(v);
+        if patches.last() != Some(&patch) {
+            patches.push(patch);
+        }
+    }
+    patches.iter().position(|p| *p == target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions() -> Vec<String> {
+        ["13.24.1", "13.23.1", "13.22.1", "13.21.1", "13.20.1"]
+            .iter()
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn trims_to_patch() {
+        assert_eq!(patch_of("13.22.1"), "13.22");
+        assert_eq!(patch_of("13.22"), "13.22");
+        assert_eq!(patch_of("lolpatch_7.20"), "lolpatch_7.20");
+    }
+
+    #[test]
+    fn counts_patches_behind() {
+        let versions = versions();
+        assert_eq!(patch_lag(&versions, "13.24.1"), Some(0));
+        assert_eq!(patch_lag(&versions, "13.24"), Some(0));
+        assert_eq!(patch_lag(&versions, "13.22.4"), Some(2));
+    }
+
+    #[test]
+    fn counts_hotfixes_as_one_patch() {
+        let versions = ["13.24.2", "13.24.1", "13.23.1"]
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(patch_lag(&versions, "13.23"), Some(1));
+    }
+
+    #[test]
+    fn unknown_patches_have_no_lag() {
+        assert_eq!(patch_lag(&versions(), "12.1.1"), None);
+        assert_eq!(patch_lag(&[], "13.24.1"), None);
+    }
+}
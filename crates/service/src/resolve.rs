@@ -0,0 +1,187 @@
+use crate::{Champion, ChampionMapResp};
+
+/// Resolves user input to a champion by id (`MonkeyKing`), key (`62`) or name (`Wukong`),
+/// ignoring case and punctuation, and tolerating small typos.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ChampionResolver {
+    champions: Vec<Champion>,
+}
+
+impl ChampionResolver {
+    pub fn new(champion_map: &ChampionMapResp) -> Self {
+        let mut champions = champion_map
+            .data
+            .values()
+            .cloned()
+            .collect::<Vec<Champion>>();
+        champions.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { champions }
+    }
+
+    /// An exact match, then the closest champion within a typo or two.
+    pub fn resolve(&self, input: &str) -> Option<&Champion> {
+        if let Some(exact) = self.resolve_exact(input) {
+            return Some(exact);
+        }
+
+        let input = normalize(input);
+        if input.is_empty() {
+            return None;
+        }
+        let max_distance = max_distance(&input);
+        self.champions
+            .iter()
+            .map(|c| (c, distance(&input, c)))
+            .filter(|(_, d)| *d <= max_distance)
+            .min_by_key(|(_, d)| *d)
+            .map(|(c, _)| c)
+    }
+
+    /// Matches the id, key or name, still ignoring case and punctuation but tolerating no typos.
+    pub fn resolve_exact(&self, input: &str) -> Option<&Champion> {
+        let input = normalize(input);
+        if input.is_empty() {
+            return None;
+        }
+
+        self.champions
+            .iter()
+            .find(|c| c.key == input || normalize(&c.id) == input || normalize(&c.name) == input)
+    }
+
+    /// Candidates for autocomplete: prefix matches, then substring matches, then close typos.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Champion> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let max_distance = max_distance(&query);
+        let mut ranked = self
+            .champions
+            .iter()
+            .filter_map(|c| {
+                let id = normalize(&c.id);
+                let name = normalize(&c.name);
+                let rank = if c.key == query || id.starts_with(&query) || name.starts_with(&query) {
+                    0
+                } else if id.contains(&query) || name.contains(&query) {
+                    1
+                } else {
+                    let d = distance(&query, c);
+                    if d > max_distance {
+                        return None;
+                    }
+                    1 + d
+                };
+                Some((rank, c))
+            })
+            .collect::<Vec<(usize, &Champion)>>();
+        ranked.sort_by_key(|(rank, _)| *rank);
+
+        ranked.into_iter().take(limit).map(|(_, c)| c).collect()
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn max_distance(input: &str) -> usize {
+    (input.chars().count() / 4).clamp(1, 2)
+}
+
+fn distance(input: &str, champion: &Champion) -> usize {
+    let id = strsim::osa_distance(input, &normalize(&champion.id));
+    let name = strsim::osa_distance(input, &normalize(&champion.name));
+    id.min(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver() -> ChampionResolver {
+        let data = [
+            ("MonkeyKing", "62", "Wukong"),
+            ("Ahri", "103", "Ahri"),
+            ("Annie", "1", "Annie"),
+            ("Kaisa", "145", "Kai'Sa"),
+            ("Aatrox", "266", "Aatrox"),
+        ]
+        .iter()
+        .map(|(id, key, name)| {
+            let champion = Champion {
+                id: id.to_string(),
+                key: key.to_string(),
+                name: name.to_string(),
+                ..Default::default()
+            };
+            (id.to_string(), champion)
+        })
+        .collect();
+        ChampionResolver::new(&ChampionMapResp {
+            data,
+            ..Default::default()
+        })
+    }
+
+    fn resolve(input: &str) -> Option<String> {
+        resolver().resolve(input).map(|c| c.id.clone())
+    }
+
+    #[test]
+    fn resolves_id_key_and_name() {
+        assert_eq!(resolve("MonkeyKing").as_deref(), Some("MonkeyKing"));
+        assert_eq!(resolve("62").as_deref(), Some("MonkeyKing"));
+        assert_eq!(resolve("wukong").as_deref(), Some("MonkeyKing"));
+        assert_eq!(resolve("kai'sa").as_deref(), Some("Kaisa"));
+        assert_eq!(resolve(" KAI SA ").as_deref(), Some("Kaisa"));
+    }
+
+    #[test]
+    fn exact_resolving_tolerates_no_typos() {
+        let resolver = resolver();
+        assert_eq!(
+            resolver.resolve_exact("monkey king").map(|c| c.id.as_str()),
+            Some("MonkeyKing")
+        );
+        assert_eq!(resolver.resolve_exact("wukon"), None);
+        assert_eq!(
+            resolver.resolve_exact("62").map(|c| c.name.as_str()),
+            Some("Wukong")
+        );
+    }
+
+    #[test]
+    fn tolerates_small_typos() {
+        assert_eq!(resolve("wukon").as_deref(), Some("MonkeyKing"));
+        // a transposition is a single typo
+        assert_eq!(resolve("aatrxo").as_deref(), Some("Aatrox"));
+    }
+
+    #[test]
+    fn rejects_distant_input() {
+        assert_eq!(resolve(""), None);
+        assert_eq!(resolve("!!"), None);
+        assert_eq!(resolve("zed"), None);
+        assert_eq!(resolve("63"), None);
+    }
+
+    #[test]
+    fn searches_prefixes_before_substrings() {
+        let resolver = resolver();
+        let found = resolver
+            .search("a", 10)
+            .into_iter()
+            .map(|c| c.id.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(found, ["Aatrox", "Ahri", "Annie", "Kaisa"]);
+
+        let limited = resolver.search("a", 1);
+        assert_eq!(limited.len(), 1);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+
+use serde_derive::Serialize;
+
+use crate::{Build, Rune, RuneStyle};
+
+/// Keystone plus one rune from each of the other three primary slots.
+const PRIMARY_PERKS: usize = 4;
+const SUB_PERKS: usize = 2;
+const STAT_SHARDS: usize = 3;
+
+/// Stat shards allowed in each row (offense, flex, defense). They aren't listed in
+/// runesReforged.json, so these are both the pre-2024 shards and the current ones.
+const STAT_SHARD_ROWS: [&[u64]; STAT_SHARDS] = [
+    // adaptive force, attack speed, ability haste
+    &[5008, 5005, 5007],
+    // adaptive force, move speed, scaling health, armor, magic resist
+    &[5008, 5010, 5001, 5002, 5003],
+    // health, tenacity and slow resist, scaling health, armor, magic resist
+    &[5011, 5013, 5001, 5002, 5003],
+];
+
+/// Body of `POST /lol-perks/v1/pages` in the League Client API.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunePage {
+    pub name: String,
+    pub primary_style_id: u64,
+    pub sub_style_id: u64,
+    pub selected_perk_ids: Vec<u64>,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunePageError {
+    UnknownStyle(u64),
+    SameStyle(u64),
+    PerkCount(usize),
+    NotInStyle { perk: u64, style: u64 },
+    DuplicateSlot { perk: u64, style: u64 },
+    InvalidShard { perk: u64, row: usize },
+}
+
+impl fmt::Display for RunePageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownStyle(style) => write!(f, "unknown style {style}"),
+            Self::SameStyle(style) => write!(f, "primary and sub style are both {style}"),
+            Self::PerkCount(n) => write!(
+                f,
+                "expected {} perks, got {n}",
+                PRIMARY_PERKS + SUB_PERKS + STAT_SHARDS
+            ),
+            Self::NotInStyle { perk, style } => write!(f, "perk {perk} is not in style {style}"),
+            Self::DuplicateSlot { perk, style } => {
+                write!(f, "perk {perk} reuses a slot of style {style}")
+            }
+            Self::InvalidShard { perk, row } => {
+                write!(f, "perk {perk} is not a stat shard of row {}", row + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RunePageError {}
+
+/// Checks perk counts and that every perk belongs to the right slot of its style.
+pub fn validate_rune(rune: &Rune, styles: &[RuneStyle]) -> Result<(), RunePageError> {
+    let find_style = |id: u64| {
+        styles
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or(RunePageError::UnknownStyle(id))
+    };
+    let primary = find_style(rune.primary_style_id)?;
+    let sub = find_style(rune.sub_style_id)?;
+    if primary.id == sub.id {
+        return Err(RunePageError::SameStyle(primary.id));
+    }
+
+    let perks = &rune.selected_perk_ids;
+    if perks.len() != PRIMARY_PERKS + SUB_PERKS + STAT_SHARDS {
+        return Err(RunePageError::PerkCount(perks.len()));
+    }
+
+    // primary perks must follow the slot order, keystone first
+    for (slot, perk) in perks[..PRIMARY_PERKS].iter().enumerate() {
+        let in_slot = primary
+            .slots
+            .get(slot)
+            .is_some_and(|s| s.runes.iter().any(|r| r.id == *perk));
+        if !in_slot {
+            return Err(RunePageError::NotInStyle {
+                perk: *perk,
+                style: primary.id,
+            });
+        }
+    }
+
+    // sub perks come from two different non-keystone slots
+    let mut used_slots = vec![];
+    for perk in perks[PRIMARY_PERKS..PRIMARY_PERKS + SUB_PERKS].iter() {
+        let slot = sub
+            .slots
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(_, s)| s.runes.iter().any(|r| r.id == *perk))
+            .map(|(idx, _)| idx)
+            .ok_or(RunePageError::NotInStyle {
+                perk: *perk,
+                style: sub.id,
+            })?;
+        if used_slots.contains(&slot) {
+            return Err(RunePageError::DuplicateSlot {
+                perk: *perk,
+                style: sub.id,
+            });
+        }
+        used_slots.push(slot);
+    }
+
+    let shards = perks[PRIMARY_PERKS + SUB_PERKS..].iter();
+    for (row, (perk, allowed)) in shards.zip(STAT_SHARD_ROWS).enumerate() {
+        if !allowed.contains(perk) {
+            return Err(RunePageError::InvalidShard { perk: *perk, row });
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts every valid rune of the builds into a named rune page, skipping invalid ones.
+/// The client has a single current page, which is the first one.
+pub fn to_rune_pages(source: &str, builds: &[Build], styles: &[RuneStyle]) -> Vec<RunePage> {
+    builds
+        .iter()
+        .flat_map(|b| b.runes.iter().enumerate())
+        .filter(|(_, rune)| validate_rune(rune, styles).is_ok())
+        .enumerate()
+        .map(|(page, (idx, rune))| RunePage {
+            name: format!("{source} {} {} {}", rune.alias, rune.position, idx + 1),
+            primary_style_id: rune.primary_style_id,
+            sub_style_id: rune.sub_style_id,
+            selected_perk_ids: rune.selected_perk_ids.clone(),
+            current: page == 0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{rune_styles, PERKS};
+
+    fn rune(perks: &[u64]) -> Rune {
+        Rune {
+            alias: "Ahri".to_string(),
+            position: "mid".to_string(),
+            primary_style_id: 8000,
+            sub_style_id: 8100,
+            selected_perk_ids: perks.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_page() {
+        assert_eq!(validate_rune(&rune(&PERKS), &rune_styles()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_perks_out_of_place() {
+        let mut perks = PERKS;
+        perks.swap(1, 2);
+        assert_eq!(
+            validate_rune(&rune(&perks), &rune_styles()),
+            Err(RunePageError::NotInStyle {
+                perk: 9104,
+                style: 8000
+            })
+        );
+
+        let mut perks = PERKS;
+        perks[5] = 8139;
+        assert_eq!(
+            validate_rune(&rune(&perks), &rune_styles()),
+            Err(RunePageError::DuplicateSlot {
+                perk: 8139,
+                style: 8100
+            })
+        );
+
+        assert_eq!(
+            validate_rune(&rune(&PERKS[..8]), &rune_styles()),
+            Err(RunePageError::PerkCount(8))
+        );
+    }
+
+    #[test]
+    fn checks_stat_shards_per_row() {
+        let mut perks = PERKS;
+        perks[8] = 5002;
+        assert_eq!(validate_rune(&rune(&perks), &rune_styles()), Ok(()));
+
+        // tenacity is a defense shard
+        let mut perks = PERKS;
+        perks[6] = 5013;
+        assert_eq!(
+            validate_rune(&rune(&perks), &rune_styles()),
+            Err(RunePageError::InvalidShard { perk: 5013, row: 0 })
+        );
+
+        let mut perks = PERKS;
+        perks[7] = 5999;
+        assert_eq!(
+            validate_rune(&rune(&perks), &rune_styles()),
+            Err(RunePageError::InvalidShard { perk: 5999, row: 1 })
+        );
+    }
+
+    #[test]
+    fn only_the_first_page_is_current() {
+        let mut invalid = PERKS;
+        invalid[0] = 1;
+        let build = Build {
+            runes: vec![rune(&invalid), rune(&PERKS), rune(&PERKS)],
+            ..Default::default()
+        };
+        let pages = to_rune_pages("op-gg", &[build], &rune_styles());
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].name, "op-gg Ahri mid 2");
+        assert!(pages[0].current);
+        assert!(!pages[1].current);
+    }
+}
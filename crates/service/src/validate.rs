@@ -0,0 +1,121 @@
+use crate::{rune_page, Build, ItemMapResp, RuneStyle};
+
+/// Checks the runes and item ids of a build against a patch's Data Dragon data,
+/// returning one warning per problem found.
+pub fn validate_build(
+    build: &Build,
+    rune_styles: &[RuneStyle],
+    items: &ItemMapResp,
+) -> Vec<String> {
+    let mut warnings = vec![];
+
+    for rune in build.runes.iter() {
+        if let Err(e) = rune_page::validate_rune(rune, rune_styles) {
+            warnings.push(format!("invalid rune {:?}: {e}", rune.selected_perk_ids));
+        }
+    }
+
+    for block in build.item_builds.iter().flat_map(|ib| ib.blocks.iter()) {
+        for item in block.items.iter().flatten() {
+            if !items.data.contains_key(&item.id) {
+                warnings.push(format!(
+                    "unknown item {} in block {}",
+                    item.id, block.type_field
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Validates each build, calling `warn` for every problem. With `strict`, builds that have any
+/// are dropped, otherwise they're only warned about.
+pub fn keep_valid_builds(
+    builds: Vec<Build>,
+    rune_styles: &[RuneStyle],
+    items: &ItemMapResp,
+    strict: bool,
+    mut warn: impl FnMut(&Build, &str),
+) -> Vec<Build> {
+    builds
+        .into_iter()
+        .filter(|b| {
+            let warnings = validate_build(b, rune_styles, items);
+            for w in warnings.iter() {
+                warn(b, w);
+            }
+            !strict || warnings.is_empty()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::fixtures::{block, build, rune_styles, PERKS};
+    use crate::ItemData;
+
+    fn items() -> ItemMapResp {
+        ItemMapResp {
+            data: HashMap::from([
+                (String::from("1001"), ItemData::default()),
+                (String::from("3020"), ItemData::default()),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    fn valid() -> Build {
+        build("mid", vec![block("Boots", &["1001", "3020"])], &PERKS)
+    }
+
+    #[test]
+    fn valid_builds_have_no_warnings() {
+        assert!(validate_build(&valid(), &rune_styles(), &items()).is_empty());
+    }
+
+    #[test]
+    fn warns_about_unknown_items() {
+        let unknown = build("mid", vec![block("Core", &["1001", "9999"])], &PERKS);
+
+        assert_eq!(
+            validate_build(&unknown, &rune_styles(), &items()),
+            ["unknown item 9999 in block Core"]
+        );
+    }
+
+    #[test]
+    fn warns_about_invalid_perks_and_shards() {
+        let mut perks = PERKS;
+        perks[1] = 8112;
+        let wrong_slot = build("mid", vec![], &perks);
+        let mut perks = PERKS;
+        perks[8] = 5005;
+        let wrong_shard = build("mid", vec![], &perks);
+
+        for b in [wrong_slot, wrong_shard] {
+            let warnings = validate_build(&b, &rune_styles(), &items());
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].starts_with("invalid rune"), "{}", warnings[0]);
+        }
+    }
+
+    #[test]
+    fn strict_drops_builds_with_problems() {
+        let invalid = build("top", vec![block("Core", &["9999"])], &PERKS);
+        let builds = vec![valid(), invalid];
+
+        let mut warned = vec![];
+        let kept = keep_valid_builds(builds.clone(), &rune_styles(), &items(), false, |b, w| {
+            warned.push(format!("{} {w}", b.position))
+        });
+        assert_eq!(kept, builds);
+        assert_eq!(warned, ["top unknown item 9999 in block Core"]);
+
+        let kept = keep_valid_builds(builds, &rune_styles(), &items(), true, |_, _| {});
+        assert_eq!(kept, [valid()]);
+    }
+}
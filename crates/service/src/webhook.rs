@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use hmac::{Hmac, KeyInit, Mac};
+use serde_derive::{Deserialize, Serialize};
+use sha2::Sha256;
+
+pub const EVENT_HEADER: &str = "X-Quicklook-Event";
+
+/// `sha256=<hex HMAC of the body>`, only sent when the webhook has a secret.
+pub const SIGNATURE_HEADER: &str = "X-Quicklook-Signature";
+
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Events a webhook can subscribe to.
+pub const EVENTS: [&str; 2] = ["source_updated", "ddragon_patch"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    SourceUpdated {
+        source: String,
+        old_version: Option<String>,
+        new_version: String,
+        champions: i64,
+    },
+    DdragonPatch {
+        old_version: Option<String>,
+        new_version: String,
+    },
+    /// sent by `webhook test`, to check a receiver
+    Ping,
+}
+
+impl WebhookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::SourceUpdated { .. } => "source_updated",
+            WebhookEvent::DdragonPatch { .. } => "ddragon_patch",
+            WebhookEvent::Ping => "ping",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryResult {
+    /// status of the last response, `None` if the receiver was never reached
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub attempts: u32,
+}
+
+impl DeliveryResult {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Checks a webhook before it is registered.
+pub fn validate(url: &str, events: &[String]) -> Result<(), String> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!("{url} is not an http(s) URL"));
+    }
+    match events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+        Some(unknown) => Err(format!(
+            "unknown event {unknown}, expected one of {EVENTS:?}"
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("any key length works");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POSTs the event as JSON, retrying with backoff on network errors, 429 and 5xx.
+pub async fn deliver(url: &str, secret: Option<&str>, event: &WebhookEvent) -> DeliveryResult {
+    let body = serde_json::to_vec(event).expect("events always serialize");
+    let client = reqwest::Client::new();
+
+    let mut result = DeliveryResult {
+        status_code: None,
+        error: None,
+        attempts: 0,
+    };
+    while result.attempts < MAX_ATTEMPTS {
+        if result.attempts > 0 {
+            tokio::time::sleep(Duration::from_secs(1 << result.attempts)).await;
+        }
+        result.attempts += 1;
+
+        let mut req = client
+            .post(url)
+            .timeout(Duration::from_secs(10))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.name())
+            .body(body.clone());
+        if let Some(secret) = secret {
+            req = req.header(SIGNATURE_HEADER, sign(secret, &body));
+        }
+
+        match req.send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                result.status_code = Some(status.as_u16());
+                if status.is_success() {
+                    result.error = None;
+                    return result;
+                }
+                result.error = Some(format!("receiver responded {status}"));
+                if !(status.is_server_error() || status.as_u16() == 429) {
+                    return result;
+                }
+            }
+            Err(e) => {
+                result.status_code = None;
+                result.error = Some(e.to_string());
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signature_depends_on_secret_and_body() {
+        let body = br#"{"event":"ping"}"#;
+        assert_eq!(sign("secret", body), sign("secret", body));
+        assert_ne!(sign("secret", body), sign("other", body));
+        assert_ne!(sign("secret", body), sign("secret", b"{}"));
+    }
+
+    #[test]
+    fn validates_url_and_events() {
+        let events = vec!["source_updated".to_string()];
+        assert!(validate("https://example.com/hook", &events).is_ok());
+        assert!(validate("https://example.com/hook", &[]).is_ok());
+        assert!(validate("ftp://example.com/hook", &events).is_err());
+        assert!(validate("https://example.com/hook", &["ping".to_string()]).is_err());
+    }
+
+    #[test]
+    fn events_are_tagged() {
+        let event = WebhookEvent::DdragonPatch {
+            old_version: None,
+            new_version: "13.24.1".to_string(),
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], "ddragon_patch");
+        assert_eq!(value["data"]["new_version"], "13.24.1");
+    }
+}